@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Vamp } from "./Vamp";
+
+export type ActiveVamp = { vamp: Vamp, 
+/**
+ * Set once the operator breaks the loop, the next cue past its end leaves it.
+ */
+broken: boolean, };
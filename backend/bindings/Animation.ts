@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AnimationStep } from "./AnimationStep";
+
+/**
+ * How the text of a line enters and leaves the displays.
+ */
+export type Animation = { 
+/**
+ * Played when the line is cued, `None` shows the text at once.
+ */
+enter: AnimationStep | null, 
+/**
+ * Played when the next line is cued, `None` removes the text at once.
+ */
+exit: AnimationStep | null, };
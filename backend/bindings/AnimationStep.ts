@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AnimationEffect } from "./AnimationEffect";
+import type { Easing } from "./Easing";
+
+export type AnimationStep = { effect: AnimationEffect, easing: Easing, duration_ms: number, delay_ms: number, 
+/**
+ * Starts the effect this much later for every following character.
+ */
+stagger_ms: number | null, };
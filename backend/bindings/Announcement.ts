@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AnnouncementLevel } from "./AnnouncementLevel";
+
+/**
+ * Free text shown on the displays instead of the song, e.g. "Pause - 15 minutes".
+ */
+export type Announcement = { id: bigint, text: string, color: string | null, level: AnnouncementLevel, 
+/**
+ * When the announcement clears by itself and the previous cue is restored.
+ */
+expires_at: string | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LineComp } from "./LineComp";
+
+/**
+ * A reusable run of lines, e.g. a chorus, that a song can play several times.
+ */
+export type Block = { id: number, name: string, lines: Array<LineComp>, };
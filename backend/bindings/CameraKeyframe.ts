@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CameraPose } from "./CameraPose";
+
+export type CameraKeyframe = { time_ms: number, 
+/**
+ * The line cued when the camera reaches the keyframe, as a position in `LoadSong::lines`.
+ */
+line_index: number, pose: CameraPose, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CameraKeyframe } from "./CameraKeyframe";
+import type { PathSample } from "./PathSample";
+
+export type CameraPath = { duration_ms: number, keyframes: Array<CameraKeyframe>, samples: Array<PathSample>, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Vector3 } from "./Vector3";
+
+/**
+ * Where the camera is and what it looks at.
+ */
+export type CameraPose = { position: Vector3, look_at: Vector3, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Vector3 } from "./Vector3";
+
+/**
+ * Someone singing or speaking in the production, e.g. one half of a duet.
+ */
+export type Character = { id: number, name: string, 
+/**
+ * Hex color the displays use for the character's lines.
+ */
+color: string | null, 
+/**
+ * Added to the position of every line the character speaks when the
+ * lines are loaded, see `character::apply_speakers`.
+ */
+position_offset: Vector3, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CountdownMode } from "./CountdownMode";
+
+/**
+ * A live countdown or clock the displays render themselves from the target time.
+ */
+export type Countdown = { id: bigint, mode: CountdownMode, label: string | null, color: string | null, 
+/**
+ * When the countdown reaches zero, `None` while paused.
+ */
+target: string | null, 
+/**
+ * Time left while paused.
+ */
+remaining_ms: bigint | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Sent to the displays every time a line is cued.
+ */
+export type Cue = { index: number | null, 
+/**
+ * Word timings of the line count from here.
+ */
+started_at: string, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Easing = { "type": "linear" } | { "type": "ease_in" } | { "type": "ease_out" } | { "type": "ease_in_out" } | { "type": "cubic_bezier", x1: number, y1: number, x2: number, y2: number, };
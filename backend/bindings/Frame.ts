@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CameraPose } from "./CameraPose";
+import type { FrameLine } from "./FrameLine";
+
+/**
+ * Everything of the active song the displays show right now.
+ */
+export type Frame = { song_id: number, index: number | null, 
+/**
+ * Where the camera starts for the current line.
+ */
+camera: CameraPose | null, 
+/**
+ * The current line last, after the previous lines it keeps.
+ */
+lines: Array<FrameLine>, started_at: string | null, };
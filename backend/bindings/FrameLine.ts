@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LineStyle } from "./LineStyle";
+import type { Span } from "./Span";
+import type { Vector3 } from "./Vector3";
+
+/**
+ * A line on the displays, styled as the song styles it.
+ */
+export type FrameLine = { line_id: number, 
+/**
+ * Position of the line in `LoadSong::lines`.
+ */
+line_index: number, current: boolean, text: string, spans: Array<Span>, style: LineStyle, position: Vector3, end_position: Vector3 | null, rotation: Vector3 | null, };
@@ -1,6 +1,56 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
-import type { AnimationType } from "./AnimationType";
-import type { Color } from "./Color";
+import type { Animation } from "./Animation";
+import type { LineKind } from "./LineKind";
+import type { Span } from "./Span";
 import type { Vector3 } from "./Vector3";
+import type { WordTiming } from "./WordTiming";
 
-export type LineComp = { id: number, line: string, position: Vector3, cam_look_at: Vector3, cam_position: Vector3, color: Color | null, keep_n_last: number, rotation: Vector3 | null, cam_rotation: Vector3 | null, text_animation: AnimationType | null, end_position: Vector3 | null, cam_end_position: Vector3 | null, cam_end_look_at: Vector3 | null, };
+export type LineComp = { id: number, 
+/**
+ * The line as written, including markup.
+ */
+line: string, 
+/**
+ * The line parsed into styled text, what the displays render.
+ */
+spans: Array<Span>, 
+/**
+ * When each word is sung, for karaoke-style highlighting. Empty if the line isn't timed.
+ */
+timings: Array<WordTiming>, kind: LineKind, 
+/**
+ * Operator-only notes, never sent to the displays.
+ */
+notes: string | null, 
+/**
+ * Tells the operator to wait for a cue from the stage before advancing past this line.
+ */
+hold: boolean, 
+/**
+ * The block the line belongs to, shared by every place the block is played.
+ */
+block_id: number, 
+/**
+ * The character singing or speaking the line.
+ */
+speaker_id: number | null, 
+/**
+ * Takes the camera and text position from the preset instead of the line's own.
+ */
+preset_id: number | null, position: Vector3, cam_look_at: Vector3, cam_position: Vector3, cam_position_duration: number | null, cam_end_position: Vector3 | null, keep_n_last: number, rotation: Vector3 | null, cam_rotation: Vector3 | null, animation: Animation | null, text_position_duration: number | null, end_position: Vector3 | null, cam_end_look_at: Vector3 | null, 
+/**
+ * Hex color, `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`.
+ */
+color: string | null, 
+/**
+ * CSS font weight, 100 to 900.
+ */
+font_weight: number | null, 
+/**
+ * Multiplier of the default text size.
+ */
+font_scale: number | null, outline_color: string | null, outline_width: number | null, 
+/**
+ * 0 is fully transparent, 1 fully opaque.
+ */
+opacity: number | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A line too long for an output, with a way to make it fit.
+ */
+export type LineFit = { song_id: number, line_id: number, line: string, rows: number, longest_row: number, 
+/**
+ * The line split into cues that fit, see `split_line`.
+ */
+proposal: Array<string>, };
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Animation } from "./Animation";
+import type { LineKind } from "./LineKind";
+import type { Vector3 } from "./Vector3";
+
+/**
+ * Changes to a line. Missing fields are left untouched, `null` clears a nullable field.
+ */
+export type LinePatch = { line?: string, kind?: LineKind, notes?: string | null, hold?: boolean, 
+/**
+ * Moves the line to another block, and with it to the song owning that block.
+ */
+block_id?: number, speaker_id?: number | null, position?: Vector3, cam_position?: Vector3, cam_position_duration?: number | null, cam_look_at?: Vector3, keep_n_last?: number, rotation?: Vector3 | null, cam_rotation?: Vector3 | null, end_position?: Vector3 | null, text_position_duration?: number | null, cam_end_position?: Vector3 | null, cam_end_look_at?: Vector3 | null, animation?: Animation | null, color?: string | null, font_weight?: number | null, font_scale?: number | null, outline_color?: string | null, outline_width?: number | null, opacity?: number | null, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How the text of a line is drawn, every unset field falls back to the song defaults.
+ */
+export type LineStyle = { 
+/**
+ * Hex color, `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`.
+ */
+color: string | null, 
+/**
+ * CSS font weight, 100 to 900.
+ */
+font_weight: number | null, 
+/**
+ * Multiplier of the default text size.
+ */
+font_scale: number | null, outline_color: string | null, outline_width: number | null, 
+/**
+ * 0 is fully transparent, 1 fully opaque.
+ */
+opacity: number | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type LintCode = "look_at_is_position" | "text_behind_camera" | "upside_down_text" | "keep_n_last_too_large" | "invalid_markup" | "empty_lyric";
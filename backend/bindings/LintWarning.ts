@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LintCode } from "./LintCode";
+import type { LintLevel } from "./LintLevel";
+
+export type LintWarning = { line_id: number, 
+/**
+ * Position of the line in `LoadSong::lines`.
+ */
+index: number, level: LintLevel, code: LintCode, message: string, };
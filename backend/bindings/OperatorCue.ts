@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ActiveVamp } from "./ActiveVamp";
+import type { LineComp } from "./LineComp";
+
+/**
+ * The cue engine state as seen by the operator, including operator-only fields.
+ */
+export type OperatorCue = { song_id: number, index: number | null, line: LineComp | null, 
+/**
+ * The loop the cursor is currently inside of, if any.
+ */
+vamp: ActiveVamp | null, 
+/**
+ * When the current line was cued.
+ */
+started_at: string | null, };
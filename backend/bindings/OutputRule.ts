@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How much text an output, e.g. a narrow side screen, fits on a line.
+ */
+export type OutputRule = { id: number, name: string, 
+/**
+ * Characters a row holds.
+ */
+max_chars: number, 
+/**
+ * Rows shown at once.
+ */
+max_rows: number, };
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CameraPose } from "./CameraPose";
+import type { Vector3 } from "./Vector3";
+
+export type PathSample = { time_ms: number, line_index: number, camera: CameraPose, 
+/**
+ * Position of the text of the current line.
+ */
+text_position: Vector3, };
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Vector3 } from "./Vector3";
+
+/**
+ * A named camera and text setup lines can share, e.g. "wide" or "close left".
+ *
+ * Unset fields leave the values of the line as they are.
+ */
+export type Preset = { id: number, name: string, cam_position: Vector3 | null, cam_look_at: Vector3 | null, cam_rotation: Vector3 | null, 
+/**
+ * Position of the text.
+ */
+position: Vector3 | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A line of a caption that CEA-608 can't show as written.
+ */
+export type SccIssue = { line_id: number | null, start_ms: bigint, text: string, message: string, };
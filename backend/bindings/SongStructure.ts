@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Block } from "./Block";
+
+/**
+ * The blocks of a song and the order they are played in.
+ */
+export type SongStructure = { song_id: number, blocks: Array<Block>, 
+/**
+ * Block ids in play order, a block may appear several times.
+ */
+order: Array<number>, };
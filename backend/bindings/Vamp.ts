@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A range of lines repeated until the operator breaks out of it.
+ */
+export type Vamp = { id: number, start_line_id: number, end_line_id: number, 
+/**
+ * Which time the song plays `start_line_id` the loop starts at, 0 for the first.
+ */
+occurrence: number, 
+/**
+ * Positions of the first and last line of the loop in `LoadSong::lines`.
+ */
+start: number, end: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A word or syllable of a line and when it is sung, counted from when the line is cued.
+ */
+export type WordTiming = { text: string, start_ms: number, 
+/**
+ * `None` keeps the word highlighted until the next one starts.
+ */
+end_ms: number | null, };
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS cue_log;
+DROP TYPE IF EXISTS cue_source;
+DROP TYPE IF EXISTS cue_event;
@@ -0,0 +1,21 @@
+-- Your SQL goes here
+CREATE TYPE cue_event AS ENUM ('set_song', 'line', 'reset');
+CREATE TYPE cue_source AS ENUM ('http', 'osc', 'ws');
+
+CREATE TABLE IF NOT EXISTS cue_log (
+  id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  event cue_event NOT NULL,
+  source cue_source NOT NULL,
+  operator TEXT,
+
+  song_id INT,
+  song_name TEXT,
+  line_index INT,
+  line TEXT,
+  skips INT,
+
+  FOREIGN KEY (song_id) REFERENCES song(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS cue_log_created_at_idx ON cue_log (created_at);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+UPDATE lines SET line = '---' WHERE kind = 'blank';
+
+ALTER TABLE lines DROP COLUMN IF EXISTS kind;
+DROP TYPE IF EXISTS line_kind;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TYPE line_kind AS ENUM ('lyric', 'blank', 'title_card', 'stage_direction', 'announcement');
+
+ALTER TABLE lines ADD COLUMN kind line_kind NOT NULL DEFAULT 'lyric';
+
+-- Blank screens used to be lines with the magic text `---`
+UPDATE lines SET kind = 'blank', line = '' WHERE line = '---';
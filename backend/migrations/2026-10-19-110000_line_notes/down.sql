@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE lines DROP COLUMN IF EXISTS hold;
+ALTER TABLE lines DROP COLUMN IF EXISTS notes;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE lines ADD COLUMN notes TEXT;
+ALTER TABLE lines ADD COLUMN hold BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS section (
+  id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  song_id INT NOT NULL,
+  name TEXT NOT NULL,
+  line_id INT NOT NULL,
+
+  FOREIGN KEY (song_id) REFERENCES song(id) ON DELETE CASCADE,
+  FOREIGN KEY (line_id) REFERENCES lines(id) ON DELETE CASCADE
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE lines DROP COLUMN IF EXISTS block_id;
+DROP TABLE IF EXISTS song_block;
+DROP TABLE IF EXISTS block;
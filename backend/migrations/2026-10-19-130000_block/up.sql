@@ -0,0 +1,27 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS block (
+  id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  song_id INT NOT NULL,
+  name TEXT NOT NULL,
+
+  FOREIGN KEY (song_id) REFERENCES song(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS song_block (
+  id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  song_id INT NOT NULL,
+  block_id INT NOT NULL,
+  position INT NOT NULL,
+
+  FOREIGN KEY (song_id) REFERENCES song(id) ON DELETE CASCADE,
+  FOREIGN KEY (block_id) REFERENCES block(id) ON DELETE CASCADE
+);
+
+ALTER TABLE lines ADD COLUMN block_id INT REFERENCES block(id) ON DELETE CASCADE;
+
+-- Every existing song becomes a single block played once
+INSERT INTO block (song_id, name) SELECT id, 'Main' FROM song;
+UPDATE lines SET block_id = block.id FROM block WHERE block.song_id = lines.song_id;
+INSERT INTO song_block (song_id, block_id, position) SELECT song_id, id, 0 FROM block;
+
+ALTER TABLE lines ALTER COLUMN block_id SET NOT NULL;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS cue_state;
+DROP TABLE IF EXISTS vamp;
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS vamp (
+  id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  song_id INT NOT NULL,
+  start_line_id INT NOT NULL,
+  end_line_id INT NOT NULL,
+
+  FOREIGN KEY (song_id) REFERENCES song(id) ON DELETE CASCADE,
+  FOREIGN KEY (start_line_id) REFERENCES lines(id) ON DELETE CASCADE,
+  FOREIGN KEY (end_line_id) REFERENCES lines(id) ON DELETE CASCADE
+);
+
+-- The cue engine cursor, so a restarted server picks up where the show was
+CREATE TABLE IF NOT EXISTS cue_state (
+  id INT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+  song_id INT NOT NULL,
+  line INT NOT NULL,
+  broken_vamp_id INT
+);
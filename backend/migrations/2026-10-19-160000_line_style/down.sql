@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE song
+  DROP COLUMN IF EXISTS color,
+  DROP COLUMN IF EXISTS font_weight,
+  DROP COLUMN IF EXISTS font_scale,
+  DROP COLUMN IF EXISTS outline_color,
+  DROP COLUMN IF EXISTS outline_width,
+  DROP COLUMN IF EXISTS opacity;
+
+ALTER TABLE lines
+  DROP COLUMN IF EXISTS color,
+  DROP COLUMN IF EXISTS font_weight,
+  DROP COLUMN IF EXISTS font_scale,
+  DROP COLUMN IF EXISTS outline_color,
+  DROP COLUMN IF EXISTS outline_width,
+  DROP COLUMN IF EXISTS opacity;
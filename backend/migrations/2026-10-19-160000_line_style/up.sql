@@ -0,0 +1,17 @@
+-- Your SQL goes here
+ALTER TABLE lines
+  ADD COLUMN color TEXT,
+  ADD COLUMN font_weight INT,
+  ADD COLUMN font_scale REAL,
+  ADD COLUMN outline_color TEXT,
+  ADD COLUMN outline_width REAL,
+  ADD COLUMN opacity REAL;
+
+-- Defaults for every line of the song that doesn't set its own
+ALTER TABLE song
+  ADD COLUMN color TEXT,
+  ADD COLUMN font_weight INT,
+  ADD COLUMN font_scale REAL,
+  ADD COLUMN outline_color TEXT,
+  ADD COLUMN outline_width REAL,
+  ADD COLUMN opacity REAL;
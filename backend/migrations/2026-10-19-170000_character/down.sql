@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE lines DROP COLUMN IF EXISTS speaker_id;
+
+DROP TABLE IF EXISTS character;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS character (
+  id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  color TEXT,
+  position_offset VECTOR(3) NOT NULL DEFAULT '[0,0,0]'
+);
+
+ALTER TABLE lines
+  ADD COLUMN speaker_id INT REFERENCES character(id) ON DELETE SET NULL;
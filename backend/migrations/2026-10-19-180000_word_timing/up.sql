@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS word_timing (
+  id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  line_id INT NOT NULL,
+  position INT NOT NULL,
+  text TEXT NOT NULL,
+  start_ms INT NOT NULL,
+  end_ms INT,
+
+  FOREIGN KEY (line_id) REFERENCES lines(id) ON DELETE CASCADE,
+  UNIQUE (line_id, position)
+);
@@ -0,0 +1,13 @@
+-- This file should undo anything in `up.sql`
+CREATE TYPE animation AS ENUM ('in', 'out', 'in_out');
+
+ALTER TABLE lines ADD COLUMN text_animation animation;
+
+UPDATE lines SET text_animation = CASE
+  WHEN animation->'enter' != 'null' AND animation->'exit' != 'null' THEN 'in_out'::animation
+  WHEN animation->'enter' != 'null' THEN 'in'::animation
+  WHEN animation->'exit' != 'null' THEN 'out'::animation
+END
+WHERE animation IS NOT NULL;
+
+ALTER TABLE lines DROP COLUMN animation;
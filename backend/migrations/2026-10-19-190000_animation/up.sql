@@ -0,0 +1,17 @@
+-- Your SQL goes here
+ALTER TABLE lines ADD COLUMN animation JSONB;
+
+-- The old animations faded the text in, out or both
+UPDATE lines SET animation = jsonb_build_object(
+  'enter', CASE WHEN text_animation IN ('in', 'in_out') THEN
+    '{"effect": {"type": "fade"}, "easing": {"type": "ease_in_out"}, "duration_ms": 500, "delay_ms": 0, "stagger_ms": null}'::jsonb
+  END,
+  'exit', CASE WHEN text_animation IN ('out', 'in_out') THEN
+    '{"effect": {"type": "fade"}, "easing": {"type": "ease_in_out"}, "duration_ms": 500, "delay_ms": 0, "stagger_ms": null}'::jsonb
+  END
+)
+WHERE text_animation IS NOT NULL;
+
+ALTER TABLE lines DROP COLUMN text_animation;
+
+DROP TYPE animation;
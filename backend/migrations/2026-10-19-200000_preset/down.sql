@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE lines DROP COLUMN IF EXISTS preset_id;
+
+DROP TABLE IF EXISTS preset;
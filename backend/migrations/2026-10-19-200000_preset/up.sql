@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS preset (
+  id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  cam_position VECTOR(3),
+  cam_look_at VECTOR(3),
+  cam_rotation VECTOR(3),
+  position VECTOR(3)
+);
+
+ALTER TABLE lines
+  ADD COLUMN preset_id INT REFERENCES preset(id) ON DELETE SET NULL;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS output_rule (
+  id INT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  max_chars INT NOT NULL CHECK (max_chars > 0),
+  max_rows INT NOT NULL CHECK (max_rows > 0)
+);
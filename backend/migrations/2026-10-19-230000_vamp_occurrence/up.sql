@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- Which time the song plays the start line, so loops work in repeated blocks
+ALTER TABLE vamp ADD COLUMN occurrence INT NOT NULL DEFAULT 0 CHECK (occurrence >= 0);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM cue_log WHERE event::text IN ('countdown', 'clear_countdown');
+
+ALTER TYPE cue_event RENAME TO cue_event_old;
+CREATE TYPE cue_event AS ENUM ('set_song', 'line', 'reset', 'announce', 'clear_announcement');
+ALTER TABLE cue_log ALTER COLUMN event TYPE cue_event USING event::text::cue_event;
+DROP TYPE cue_event_old;
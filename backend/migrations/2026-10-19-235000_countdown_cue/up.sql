@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TYPE cue_event ADD VALUE IF NOT EXISTS 'countdown';
+ALTER TYPE cue_event ADD VALUE IF NOT EXISTS 'clear_countdown';
@@ -0,0 +1,133 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{TimeDelta, Utc};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    cue_log::{record_override, CueEvent, Operator},
+    output::publish,
+    style::valid_color,
+    types::{Announcement, AnnouncementLevel},
+    Store,
+};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Deserialize, Debug)]
+pub struct NewAnnouncement {
+    text: String,
+    color: Option<String>,
+    #[serde(default)]
+    level: AnnouncementLevel,
+    /// Clears the announcement after this many seconds, restoring the previous cue.
+    timeout_secs: Option<u64>,
+}
+
+pub async fn set_announcement(
+    State(state): State<Store>,
+    Operator(operator): Operator,
+    Json(body): Json<NewAnnouncement>,
+) -> Result<Json<Announcement>, (StatusCode, &'static str)> {
+    let text = body.text.trim().to_string();
+
+    if text.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Announcement text is empty"));
+    }
+
+    if body
+        .color
+        .as_deref()
+        .is_some_and(|color| !valid_color(color))
+    {
+        return Err((StatusCode::BAD_REQUEST, "Invalid announcement color"));
+    }
+
+    let expires_at = match body.timeout_secs {
+        Some(secs) => Some(
+            i64::try_from(secs)
+                .ok()
+                .and_then(TimeDelta::try_seconds)
+                .and_then(|timeout| Utc::now().checked_add_signed(timeout))
+                .ok_or((StatusCode::BAD_REQUEST, "Announcement timeout out of range"))?,
+        ),
+        None => None,
+    };
+
+    let mut output = state.output.write().await;
+
+    if output
+        .announcement
+        .as_ref()
+        .is_some_and(|current| current.level > body.level)
+    {
+        return Err((StatusCode::CONFLICT, "An emergency announcement is showing"));
+    }
+
+    let announcement = Announcement {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        text: text.clone(),
+        color: body.color,
+        level: body.level,
+        expires_at,
+    };
+
+    info!("Showing announcement: {:?}", announcement);
+
+    output.announcement = Some(announcement.clone());
+    publish(&state, &output);
+    let _ = state.announcement_ch.send(Some(announcement.clone()));
+    drop(output);
+
+    if let Some(secs) = body.timeout_secs {
+        let state = state.clone();
+        let id = announcement.id;
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            clear_announcement(&state, Some(id), None).await;
+        });
+    }
+
+    record_override(&state, CueEvent::Announce, Some(text), operator).await;
+
+    Ok(Json(announcement))
+}
+
+/// Clears the announcement and restores the previous cue. When `id` is given
+/// the announcement is only cleared if it hasn't been replaced in the meantime.
+async fn clear_announcement(state: &Store, id: Option<u64>, operator: Option<String>) -> bool {
+    let mut output = state.output.write().await;
+
+    match (&output.announcement, id) {
+        (None, _) => return false,
+        (Some(current), Some(id)) if current.id != id => return false,
+        _ => {}
+    }
+
+    info!("Clearing announcement");
+
+    output.announcement = None;
+    publish(state, &output);
+    let _ = state.announcement_ch.send(None);
+    drop(output);
+
+    record_override(state, CueEvent::ClearAnnouncement, None, operator).await;
+
+    true
+}
+
+pub async fn delete_announcement(
+    State(state): State<Store>,
+    Operator(operator): Operator,
+) -> StatusCode {
+    if clear_announcement(&state, None, operator).await {
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
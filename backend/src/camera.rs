@@ -0,0 +1,324 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use ts_rs::TS;
+
+use crate::{
+    structure::load_song_lines,
+    types::{LineComp, Vector3},
+    Store,
+};
+
+/// How long the camera takes to reach a line when `cam_position_duration` isn't set.
+pub const DEFAULT_CAM_DURATION_MS: u32 = 1000;
+
+/// Nominal time every line stays up, cues are live so there is no real timeline.
+pub const DEFAULT_LINE_MS: u32 = 4000;
+
+const DEFAULT_STEP_MS: u32 = 100;
+const MIN_STEP_MS: u32 = 10;
+const MAX_SAMPLES: usize = 100_000;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum Interpolation {
+    Linear,
+    /// Smooth curve through every keyframe of the camera.
+    CatmullRom,
+    /// Linear, but slowing down into and out of every keyframe.
+    #[default]
+    Eased,
+}
+
+/// Where the camera is and what it looks at.
+#[derive(Debug, Serialize, Clone, PartialEq, Default, TS)]
+#[ts(export)]
+pub struct CameraPose {
+    pub position: Vector3,
+    pub look_at: Vector3,
+}
+
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct CameraKeyframe {
+    pub time_ms: u32,
+    /// The line cued when the camera reaches the keyframe, as a position in `LoadSong::lines`.
+    pub line_index: usize,
+    pub pose: CameraPose,
+}
+
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct PathSample {
+    pub time_ms: u32,
+    pub line_index: usize,
+    pub camera: CameraPose,
+    /// Position of the text of the current line.
+    pub text_position: Vector3,
+}
+
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct CameraPath {
+    pub duration_ms: u32,
+    pub keyframes: Vec<CameraKeyframe>,
+    pub samples: Vec<PathSample>,
+}
+
+fn lerp(a: &Vector3, b: &Vector3, t: f32) -> Vector3 {
+    Vector3 {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+        z: a.z + (b.z - a.z) * t,
+    }
+}
+
+/// Uniform Catmull-Rom between `p1` and `p2`, with `p0` and `p3` the points around them.
+fn catmull_rom(p0: &Vector3, p1: &Vector3, p2: &Vector3, p3: &Vector3, t: f32) -> Vector3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let axis = |a: f32, b: f32, c: f32, d: f32| {
+        0.5 * (2.0 * b
+            + (c - a) * t
+            + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+            + (3.0 * b - a - 3.0 * c + d) * t3)
+    };
+
+    Vector3 {
+        x: axis(p0.x, p1.x, p2.x, p3.x),
+        y: axis(p0.y, p1.y, p2.y, p3.y),
+        z: axis(p0.z, p1.z, p2.z, p3.z),
+    }
+}
+
+fn ease_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+/// When each line is cued on the nominal timeline.
+fn cue_time(index: usize, line_ms: u32) -> u32 {
+    (index as u32).saturating_mul(line_ms)
+}
+
+/// The poses the camera passes through while a song plays.
+///
+/// On every cue the camera moves to the line's `cam_position` and `cam_look_at`
+/// within `cam_position_duration`, then drifts to `cam_end_position` and
+/// `cam_end_look_at`, if set, until the next cue. The first line starts in place.
+pub fn camera_keyframes(song_lines: &[LineComp], line_ms: u32) -> Vec<CameraKeyframe> {
+    let mut keyframes = Vec::new();
+
+    for (line_index, line) in song_lines.iter().enumerate() {
+        let cued_at = cue_time(line_index, line_ms);
+        let duration = match line_index {
+            0 => 0,
+            _ => line
+                .cam_position_duration
+                .map_or(DEFAULT_CAM_DURATION_MS, |ms| ms.max(0) as u32)
+                .min(line_ms),
+        };
+
+        let start = CameraPose {
+            position: line.cam_position.clone(),
+            look_at: line.cam_look_at.clone(),
+        };
+        let end = CameraPose {
+            position: line
+                .cam_end_position
+                .clone()
+                .unwrap_or(start.position.clone()),
+            look_at: line
+                .cam_end_look_at
+                .clone()
+                .unwrap_or(start.look_at.clone()),
+        };
+
+        // Without a transition this shares its time with the previous keyframe, a cut
+        keyframes.push(CameraKeyframe {
+            time_ms: cued_at.saturating_add(duration),
+            line_index,
+            pose: start,
+        });
+
+        keyframes.push(CameraKeyframe {
+            time_ms: cue_time(line_index + 1, line_ms),
+            line_index,
+            pose: end,
+        });
+    }
+
+    keyframes
+}
+
+/// Where the camera is at `time_ms`, `keyframes` must not be empty.
+///
+/// Of two keyframes at the same time, the later one applies from that time on.
+pub fn camera_at(keyframes: &[CameraKeyframe], time_ms: u32, mode: Interpolation) -> CameraPose {
+    let next = keyframes
+        .iter()
+        .position(|kf| kf.time_ms > time_ms)
+        .unwrap_or(keyframes.len());
+
+    if next == 0 {
+        return keyframes[0].pose.clone();
+    }
+    if next == keyframes.len() {
+        return keyframes[next - 1].pose.clone();
+    }
+
+    let from = &keyframes[next - 1];
+    let to = &keyframes[next];
+
+    // Holding still between cues, a spline would wobble here
+    if from.pose == to.pose {
+        return from.pose.clone();
+    }
+
+    let t = (time_ms - from.time_ms) as f32 / (to.time_ms - from.time_ms) as f32;
+
+    match mode {
+        Interpolation::Linear => CameraPose {
+            position: lerp(&from.pose.position, &to.pose.position, t),
+            look_at: lerp(&from.pose.look_at, &to.pose.look_at, t),
+        },
+        Interpolation::Eased => CameraPose {
+            position: lerp(&from.pose.position, &to.pose.position, ease_in_out(t)),
+            look_at: lerp(&from.pose.look_at, &to.pose.look_at, ease_in_out(t)),
+        },
+        Interpolation::CatmullRom => {
+            let before = &keyframes[next.saturating_sub(2)].pose;
+            let after = &keyframes[(next + 1).min(keyframes.len() - 1)].pose;
+
+            CameraPose {
+                position: catmull_rom(
+                    &before.position,
+                    &from.pose.position,
+                    &to.pose.position,
+                    &after.position,
+                    t,
+                ),
+                look_at: catmull_rom(
+                    &before.look_at,
+                    &from.pose.look_at,
+                    &to.pose.look_at,
+                    &after.look_at,
+                    t,
+                ),
+            }
+        }
+    }
+}
+
+/// Where the text of a line is `elapsed_ms` after it was cued.
+///
+/// The text moves from `position` to `end_position` within `text_position_duration`,
+/// or the whole time the line is up.
+pub fn text_position_at(
+    line: &LineComp,
+    elapsed_ms: u32,
+    line_ms: u32,
+    mode: Interpolation,
+) -> Vector3 {
+    let Some(end_position) = &line.end_position else {
+        return line.position.clone();
+    };
+
+    let duration = line
+        .text_position_duration
+        .map_or(line_ms, |ms| ms.max(0) as u32);
+    if duration == 0 {
+        return end_position.clone();
+    }
+
+    let t = (elapsed_ms as f32 / duration as f32).min(1.0);
+    let t = match mode {
+        Interpolation::Linear | Interpolation::CatmullRom => t,
+        Interpolation::Eased => ease_in_out(t),
+    };
+
+    lerp(&line.position, end_position, t)
+}
+
+/// Samples the camera and text of a song every `step_ms` on the nominal timeline.
+pub fn camera_path(
+    song_lines: &[LineComp],
+    mode: Interpolation,
+    line_ms: u32,
+    step_ms: u32,
+) -> CameraPath {
+    let keyframes = camera_keyframes(song_lines, line_ms);
+    let duration_ms = cue_time(song_lines.len(), line_ms);
+
+    let samples = match keyframes.is_empty() {
+        true => Vec::new(),
+        false => (0..=duration_ms)
+            .step_by(step_ms.max(1) as usize)
+            .map(|time_ms| {
+                let line_index = ((time_ms / line_ms.max(1)) as usize).min(song_lines.len() - 1);
+                let elapsed_ms = time_ms - cue_time(line_index, line_ms);
+
+                PathSample {
+                    time_ms,
+                    line_index,
+                    camera: camera_at(&keyframes, time_ms, mode),
+                    text_position: text_position_at(
+                        &song_lines[line_index],
+                        elapsed_ms,
+                        line_ms,
+                        mode,
+                    ),
+                }
+            })
+            .collect(),
+    };
+
+    CameraPath {
+        duration_ms,
+        keyframes,
+        samples,
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PathRequest {
+    id: i32,
+    #[serde(default)]
+    mode: Interpolation,
+    line_ms: Option<u32>,
+    step_ms: Option<u32>,
+}
+
+pub async fn get_camera_path(
+    State(state): State<Store>,
+    Query(req): Query<PathRequest>,
+) -> Result<Json<CameraPath>, (StatusCode, &'static str)> {
+    let line_ms = req.line_ms.unwrap_or(DEFAULT_LINE_MS).max(1);
+    let step_ms = req.step_ms.unwrap_or(DEFAULT_STEP_MS).max(MIN_STEP_MS);
+
+    let pool = state.pool.get().await.unwrap();
+
+    let song_lines = pool
+        .interact(move |con| load_song_lines(con, req.id))
+        .await
+        .unwrap()
+        .map_err(|e| {
+            error!("Failed to load lines of song {}: {}", req.id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load song")
+        })?;
+
+    let samples = cue_time(song_lines.len(), line_ms) / step_ms;
+    if samples as usize > MAX_SAMPLES {
+        return Err((StatusCode::BAD_REQUEST, "Too many samples, raise step_ms"));
+    }
+
+    Ok(Json(camera_path(&song_lines, req.mode, line_ms, step_ms)))
+}
@@ -0,0 +1,208 @@
+use std::fmt::Write;
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::{
+    cue_log::{load_entries, CueEvent, CueLogEntry},
+    hls::cue_text,
+    scc::scc_response,
+    Store,
+};
+
+/// A caption, times in milliseconds from the start of the recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caption {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    /// The line of the song shown, when captions come from a song.
+    pub line_id: Option<i32>,
+}
+
+/// What the displays showed over the course of the log, in recording time.
+///
+/// Announcements show over the song until cleared, and so do countdowns as
+/// their label, or nothing without one, below announcements like on the
+/// displays. Resets and new songs clear the song's line, and blank cues end
+/// the caption before them. The last caption ends at `end`, or the last entry
+/// if not given. Captions before
+/// `recording_start` are dropped, or cut if they were still showing.
+pub fn build_captions(
+    entries: &[CueLogEntry],
+    recording_start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+) -> Vec<Caption> {
+    let mut captions = Vec::new();
+    let mut song_line = String::new();
+    let mut announcement: Option<String> = None;
+    let mut countdown: Option<String> = None;
+    let mut showing: Option<(DateTime<Utc>, String)> = None;
+
+    let mut close = |showing: &mut Option<(DateTime<Utc>, String)>, at: DateTime<Utc>| {
+        let Some((start, text)) = showing.take() else {
+            return;
+        };
+
+        let start_ms = (start - recording_start).num_milliseconds().max(0);
+        let end_ms = (at - recording_start).num_milliseconds();
+        if end_ms > start_ms {
+            captions.push(Caption {
+                start_ms,
+                end_ms,
+                text,
+                line_id: None,
+            });
+        }
+    };
+
+    for entry in entries {
+        match entry.event {
+            CueEvent::Line => song_line = entry.line.clone().unwrap_or_default(),
+            CueEvent::SetSong | CueEvent::Reset => song_line.clear(),
+            CueEvent::Announce => announcement = entry.line.clone(),
+            CueEvent::ClearAnnouncement => announcement = None,
+            CueEvent::Countdown => countdown = Some(entry.line.clone().unwrap_or_default()),
+            CueEvent::ClearCountdown => countdown = None,
+        }
+
+        let shown = announcement
+            .as_deref()
+            .or(countdown.as_deref())
+            .unwrap_or(&song_line)
+            .trim();
+        if showing.as_ref().map_or("", |(_, text)| text.as_str()) == shown {
+            continue;
+        }
+
+        close(&mut showing, entry.created_at);
+        if !shown.is_empty() {
+            showing = Some((entry.created_at, shown.to_string()));
+        }
+    }
+
+    if let Some(last) = entries.last() {
+        close(&mut showing, end.unwrap_or(last.created_at));
+    }
+
+    captions
+}
+
+fn timestamp(ms: i64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+pub fn to_srt(captions: &[Caption]) -> String {
+    let mut srt = String::new();
+
+    for (i, caption) in captions.iter().enumerate() {
+        let text = caption
+            .text
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            timestamp(caption.start_ms, ','),
+            timestamp(caption.end_ms, ','),
+            text
+        );
+    }
+
+    srt
+}
+
+pub fn to_vtt(captions: &[Caption]) -> String {
+    let mut vtt = "WEBVTT\n".to_string();
+
+    for caption in captions {
+        let _ = write!(
+            vtt,
+            "\n{} --> {}\n{}\n",
+            timestamp(caption.start_ms, '.'),
+            timestamp(caption.end_ms, '.'),
+            cue_text(&caption.text)
+        );
+    }
+
+    vtt
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionFormat {
+    #[default]
+    Srt,
+    Vtt,
+    /// CEA-608 for broadcast, see `scc::encode_scc`.
+    Scc,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CaptionRequest {
+    /// When the recording started, captions are timed from here.
+    recording_start: DateTime<Utc>,
+    /// When the recording ended, closing the last caption.
+    recording_end: Option<DateTime<Utc>>,
+    #[serde(default)]
+    format: CaptionFormat,
+    /// Lists what the format can't show instead, for SCC.
+    #[serde(default)]
+    report: bool,
+}
+
+/// Captions for the recording of a show, built from the cue log.
+pub async fn get_captions(
+    State(state): State<Store>,
+    Query(req): Query<CaptionRequest>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    if req
+        .recording_end
+        .is_some_and(|end| end <= req.recording_start)
+    {
+        return Err((StatusCode::BAD_REQUEST, "Recording ends before it starts"));
+    }
+
+    let pool = state.pool.get().await.unwrap();
+
+    // Whatever showed when the recording started was cued before it
+    let entries = load_entries(&pool, None, req.recording_end).await?;
+    let captions = build_captions(&entries, req.recording_start, req.recording_end);
+
+    let (body, content_type, extension) = match req.format {
+        CaptionFormat::Srt => (to_srt(&captions), "application/x-subrip", "srt"),
+        CaptionFormat::Vtt => (to_vtt(&captions), "text/vtt", "vtt"),
+        CaptionFormat::Scc => return Ok(scc_response(&captions, req.report, "captions.scc")),
+    };
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                format!("{content_type}; charset=utf-8"),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"captions.{extension}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
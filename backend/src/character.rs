@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use axum::{extract::State, http::StatusCode, Json};
+use diesel::{
+    ExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl, SelectableHelper,
+};
+use pgvector::Vector;
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::{
+    schema::{character, lines},
+    structure::load_song_lines,
+    style::valid_color,
+    types::{Character, DbCharacter, LineComp, Vector3},
+    Store,
+};
+
+/// Loads the characters speaking any of `song_lines`.
+pub fn load_characters(
+    con: &mut PgConnection,
+    song_lines: &[LineComp],
+) -> QueryResult<Vec<Character>> {
+    let speaker_ids = song_lines
+        .iter()
+        .filter_map(|line| line.speaker_id)
+        .collect::<Vec<_>>();
+
+    Ok(character::table
+        .select(DbCharacter::as_select())
+        .filter(character::id.eq_any(&speaker_ids))
+        .order(character::id.asc())
+        .load(con)?
+        .into_iter()
+        .map(Character::from)
+        .collect())
+}
+
+fn offset(value: &Vector3, by: &Vector3) -> Vector3 {
+    Vector3 {
+        x: value.x + by.x,
+        y: value.y + by.y,
+        z: value.z + by.z,
+    }
+}
+
+/// Moves the text of lines with a speaker by the speaker's `position_offset`.
+///
+/// Runs whenever lines are loaded for the displays, after the presets, so the
+/// offset is never saved into the line itself.
+pub fn apply_speakers(con: &mut PgConnection, song_lines: &mut [LineComp]) -> QueryResult<()> {
+    if song_lines.iter().all(|line| line.speaker_id.is_none()) {
+        return Ok(());
+    }
+
+    let offsets = load_characters(con, song_lines)?
+        .into_iter()
+        .map(|character| (character.id, character.position_offset))
+        .collect::<HashMap<_, _>>();
+
+    for line in song_lines {
+        let Some(by) = line.speaker_id.and_then(|id| offsets.get(&id)) else {
+            continue;
+        };
+
+        line.position = offset(&line.position, by);
+        line.end_position = line
+            .end_position
+            .as_ref()
+            .map(|end_position| offset(end_position, by));
+    }
+
+    Ok(())
+}
+
+pub async fn get_characters(
+    State(state): State<Store>,
+) -> Result<Json<Vec<Character>>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(|con| {
+            character::table
+                .select(DbCharacter::as_select())
+                .order(character::name.asc())
+                .load(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(characters) => Ok(Json(characters.into_iter().map(Character::from).collect())),
+        Err(e) => {
+            error!("Failed to load characters: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load characters",
+            ))
+        }
+    }
+}
+
+/// Checks a character sent by a client and returns its trimmed name.
+fn validate(body: &Character) -> Result<String, (StatusCode, &'static str)> {
+    let name = body.name.trim().to_string();
+
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Character name is empty"));
+    }
+    if body
+        .color
+        .as_deref()
+        .is_some_and(|color| !valid_color(color))
+    {
+        return Err((StatusCode::BAD_REQUEST, "Invalid character color"));
+    }
+
+    Ok(name)
+}
+
+pub async fn add_character(
+    State(state): State<Store>,
+    Json(body): Json<Character>,
+) -> Result<Json<Character>, (StatusCode, &'static str)> {
+    let name = validate(&body)?;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            diesel::insert_into(character::table)
+                .values((
+                    character::name.eq(name),
+                    character::color.eq(body.color),
+                    character::position_offset.eq::<Vector>(body.position_offset.into()),
+                ))
+                .returning(DbCharacter::as_returning())
+                .get_result(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(added) => {
+            info!("Added character with id: {}", added.id);
+            Ok(Json(Character::from(added)))
+        }
+        Err(e) => {
+            error!("Failed to add character: {}", e);
+            Err((StatusCode::CONFLICT, "Failed to add character"))
+        }
+    }
+}
+
+pub async fn edit_character(
+    State(state): State<Store>,
+    Json(body): Json<Character>,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    let name = validate(&body)?;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let id = body.id;
+
+    let res = pool
+        .interact(move |con| {
+            diesel::update(character::table.find(body.id))
+                .set((
+                    character::name.eq(name),
+                    character::color.eq(body.color),
+                    character::position_offset.eq::<Vector>(body.position_offset.into()),
+                ))
+                .execute(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(0) => Err((StatusCode::NOT_FOUND, "Character not found")),
+        Ok(_) => {
+            info!("Updated character with id: {}", id);
+            Ok(StatusCode::OK)
+        }
+        Err(e) => {
+            error!("Failed to update character {}: {}", id, e);
+            Err((StatusCode::CONFLICT, "Failed to update character"))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CharacterRequest {
+    id: i32,
+}
+
+pub async fn delete_character(
+    State(state): State<Store>,
+    Json(body): Json<CharacterRequest>,
+) -> StatusCode {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| diesel::delete(character::table.find(body.id)).execute(con))
+        .await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        error!("Failed to delete character with id: {}", body.id);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    info!("Deleted character with id: {}", body.id);
+
+    StatusCode::OK
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AssignSpeaker {
+    song_id: i32,
+    /// Positions of the first and last line in `LoadSong::lines`, both inclusive.
+    start: usize,
+    end: usize,
+    /// `None` clears the speaker of the lines.
+    speaker_id: Option<i32>,
+}
+
+/// Sets the speaker of a range of lines of a song.
+///
+/// Lines of blocks played several times are shared, so every repeat of the
+/// block gets the speaker too.
+pub async fn assign_speaker(
+    State(state): State<Store>,
+    Json(body): Json<AssignSpeaker>,
+) -> Result<Json<usize>, (StatusCode, &'static str)> {
+    if body.start > body.end {
+        return Err((StatusCode::BAD_REQUEST, "Range starts after it ends"));
+    }
+
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            let song_lines = load_song_lines(con, body.song_id)?;
+
+            let Some(range) = song_lines.get(body.start..=body.end) else {
+                return QueryResult::Ok(None);
+            };
+            let line_ids = range.iter().map(|line| line.id).collect::<Vec<_>>();
+
+            diesel::update(lines::table.filter(lines::id.eq_any(&line_ids)))
+                .set(lines::speaker_id.eq(body.speaker_id))
+                .execute(con)
+                .map(Some)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(Some(updated)) => {
+            info!(
+                "Assigned speaker {:?} to {} lines of song {}",
+                body.speaker_id, updated, body.song_id
+            );
+            Ok(Json(updated))
+        }
+        Ok(None) => Err((StatusCode::BAD_REQUEST, "Range is outside of the song")),
+        Err(e) => {
+            error!("Failed to assign speaker: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to assign speaker",
+            ))
+        }
+    }
+}
@@ -3,18 +3,34 @@ use axum::{
     http::StatusCode,
     Form, Json,
 };
+use chrono::Utc;
 use diesel::{
-    dsl::exists, select, BelongingToDsl, Connection, ExpressionMethods, QueryDsl, Queryable,
-    RunQueryDsl, Selectable, SelectableHelper,
+    Connection, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, QueryResult,
+    Queryable, RunQueryDsl, Selectable, SelectableHelper,
 };
 use pgvector::Vector;
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 
 use crate::{
+    character::load_characters,
+    cue_log::{record_cue, CueEvent, CueSource, NewCueLogEntry, Operator},
+    cue_state::save_cue_state,
+    fit::{load_rule_named, split_long_lines},
+    frame::{shown_index, song_frame},
+    layout::{placements, Layout, DEFAULT_CAM_OFFSET},
+    markup::{parse_markup, sanitize},
+    output::{show_frame, show_song_line},
     schema::*,
-    types::{DbLineComp, DbLoadSong, LineComp, LoadSong, NewDbLineComp, Vector3},
-    Store,
+    structure::{load_song_lines, new_lines, parse_structure, play_position, validate_lines},
+    style::load_song_style,
+    timing::load_timings,
+    types::{
+        Cue, DbLineComp, DbLoadSong, DbSection, LineComp, LineStyle, LoadSong, OperatorCue,
+        Section, Vamp,
+    },
+    vamp::{active_vamp, leave_vamps, load_vamps, wrap_target},
+    ActiveSong, Store,
 };
 
 #[derive(Serialize, Selectable, Queryable, Debug)]
@@ -28,62 +44,106 @@ pub struct SongNames {
 pub struct FormSong {
     pub name: String,
     pub lines: String,
+    /// Name of the layout generator placing the imported lines, see `Layout::named`.
+    pub layout: Option<String>,
+    pub seed: Option<u64>,
+    /// Name of an output rule, lines too long for it are split into several cues.
+    pub split_for: Option<String>,
 }
 
-pub async fn add_song(State(state): State<Store>, Form(song): Form<FormSong>) -> &'static str {
+pub async fn add_song(
+    State(state): State<Store>,
+    Form(song): Form<FormSong>,
+) -> Result<&'static str, (StatusCode, &'static str)> {
     let pool = state.pool.get().await.unwrap();
 
-    let lines_comp = song
-        .lines
-        .split("\n")
-        .map(|line| line.trim().to_string())
-        .collect::<Vec<_>>();
+    let (mut blocks, order) = parse_structure(&song.lines);
 
-    let comps = lines_comp.into_iter().map(|line| LineComp {
-        line: line.clone(),
-        cam_position: Vector3 {
-            x: 0.0,
-            y: 10.0,
-            z: 150.0,
-        },
-        ..Default::default()
-    });
+    for import in &blocks {
+        validate_lines(&import.lines)?;
+    }
+
+    if let Some(name) = song.split_for.clone().filter(|name| !name.is_empty()) {
+        let rule = pool
+            .interact(move |con| load_rule_named(con, &name))
+            .await
+            .unwrap()
+            .map_err(|e| {
+                error!("Failed to load output rule: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load output")
+            })?
+            .ok_or((StatusCode::BAD_REQUEST, "Unknown output"))?;
+
+        for import in &mut blocks {
+            import.lines = split_long_lines(std::mem::take(&mut import.lines), &rule);
+        }
+    }
+
+    let layout = match song.layout.as_deref().filter(|name| !name.is_empty()) {
+        Some(name) => {
+            Layout::named(name, song.seed).ok_or((StatusCode::BAD_REQUEST, "Unknown layout"))?
+        }
+        None => Layout::Stack,
+    };
+    let line_count = blocks.iter().map(|import| import.lines.len()).sum();
+    let mut placed = placements(&layout, line_count, &DEFAULT_CAM_OFFSET).into_iter();
 
-    let _ = pool
-        .interact(|con| {
+    let res = pool
+        .interact(move |con| {
             con.transaction(|tran| {
                 let song_id = diesel::insert_into(song::table)
                     .values(song::name.eq(song.name))
                     .returning(song::id)
-                    .get_result::<i32>(tran)
-                    .unwrap();
-
-                let lines = comps
-                    .into_iter()
-                    .map(|val| NewDbLineComp {
-                        line: val.line,
-                        song_id,
-                        position: val.position.into(),
-                        end_position: None,
-                        keep_n_last: 0,
-                        cam_position: val.cam_position.into(),
-                        cam_look_at: val.cam_look_at.into(),
-                        cam_end_position: None,
-                        cam_end_look_at: None,
+                    .get_result::<i32>(tran)?;
+
+                let mut block_ids = Vec::new();
+
+                for import in blocks {
+                    let block_id = diesel::insert_into(block::table)
+                        .values((block::song_id.eq(song_id), block::name.eq(import.name)))
+                        .returning(block::id)
+                        .get_result::<i32>(tran)?;
+
+                    let mut rows = new_lines(song_id, block_id, import.lines);
+                    for (row, placement) in rows.iter_mut().zip(placed.by_ref()) {
+                        placement.apply(row);
+                    }
+
+                    diesel::insert_into(lines::table)
+                        .values(&rows)
+                        .execute(tran)?;
+
+                    block_ids.push(block_id);
+                }
+
+                let structure = order
+                    .iter()
+                    .enumerate()
+                    .map(|(position, &index)| {
+                        (
+                            song_block::song_id.eq(song_id),
+                            song_block::block_id.eq(block_ids[index]),
+                            song_block::position.eq(position as i32),
+                        )
                     })
                     .collect::<Vec<_>>();
 
-                diesel::insert_into(super::schema::lines::table)
-                    .values(&lines)
-                    .execute(tran)
-                    .unwrap();
+                diesel::insert_into(song_block::table)
+                    .values(&structure)
+                    .execute(tran)?;
+
                 diesel::result::QueryResult::Ok(())
             })
         })
         .await
         .unwrap();
 
-    "Hello, World!"
+    if let Err(e) = res {
+        error!("Failed to add song: {}", e);
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to add song"));
+    }
+
+    Ok("Hello, World!")
 }
 
 #[derive(Deserialize, Debug)]
@@ -105,13 +165,14 @@ pub async fn get_song(
                 .get_result(con)
                 .unwrap();
 
-            let line_res: Vec<DbLineComp> = DbLineComp::belonging_to(&song_join)
-                .order(lines::id.asc())
-                .select(DbLineComp::as_select())
-                .load(con)
-                .unwrap();
+            let line_res = load_song_lines(con, song_join.id).unwrap();
 
-            (song_join, line_res)
+            let sections = load_sections(con, song_join.id, &line_res).unwrap();
+            let vamps = load_vamps(con, song_join.id, &line_res).unwrap();
+            let style = load_song_style(con, song_join.id).unwrap();
+            let characters = load_characters(con, &line_res).unwrap();
+
+            (song_join, line_res, sections, vamps, style, characters)
         })
         .await
         .unwrap();
@@ -119,7 +180,11 @@ pub async fn get_song(
     Ok(Json(LoadSong {
         id: query_song.0.id,
         title: query_song.0.name,
-        lines: query_song.1.into_iter().map(LineComp::from).collect(),
+        lines: query_song.1,
+        sections: query_song.2,
+        vamps: query_song.3,
+        style: query_song.4,
+        characters: query_song.5,
     }))
 }
 
@@ -131,7 +196,7 @@ pub async fn get_all_songs(
     let pool = state.pool.get().await.unwrap();
 
     let song_res = pool
-        .interact(|con| song.load::<SongNames>(con))
+        .interact(|con| song.select(SongNames::as_select()).load(con))
         .await
         .unwrap()
         .unwrap();
@@ -139,8 +204,48 @@ pub async fn get_all_songs(
     Ok(Json(song_res))
 }
 
+pub fn song_name(con: &mut PgConnection, song_id: i32) -> QueryResult<Option<String>> {
+    song::table
+        .find(song_id)
+        .select(song::name)
+        .first::<String>(con)
+        .optional()
+}
+
+/// Loads the sections of a song, ordered by where they start in `song_lines`.
+fn load_sections(
+    con: &mut PgConnection,
+    song_id: i32,
+    song_lines: &[LineComp],
+) -> QueryResult<Vec<Section>> {
+    let rows = section::table
+        .filter(section::song_id.eq(song_id))
+        .select(DbSection::as_select())
+        .load(con)?;
+
+    let mut sections = rows
+        .into_iter()
+        .filter_map(|row| {
+            let index = play_position(song_lines, row.line_id, row.occurrence)?;
+
+            Some(Section {
+                id: row.id,
+                name: row.name,
+                line_id: row.line_id,
+                occurrence: row.occurrence,
+                index: index as u32,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    sections.sort_by_key(|section| section.index);
+
+    Ok(sections)
+}
+
 pub async fn set_active_song(
     State(state): State<Store>,
+    Operator(operator): Operator,
     Json(song_req): Json<SongRequest>,
 ) -> StatusCode {
     info!("Setting active song to: {:?}", song_req);
@@ -150,43 +255,65 @@ pub async fn set_active_song(
     let table_id = song_req.id;
     let table_id_2 = song_req.id;
 
-    let res = pool
-        .interact(move |con| {
-            select(exists(song::table.filter(song::id.eq(table_id)))).get_result::<bool>(con)
-        })
+    let name = pool
+        .interact(move |con| song_name(con, table_id))
         .await
         .unwrap()
         .unwrap();
 
-    if !res {
+    let Some(name) = name else {
         return StatusCode::NOT_FOUND;
-    }
+    };
 
     let mut active_song = state.active_song.write().await;
     active_song.id = song_req.id;
     active_song.line = 0;
+    active_song.broken_vamp = None;
+    active_song.cued_at = None;
 
-    let lines_res = pool
+    let (lines_res, sections, vamps, style, characters) = pool
         .interact(move |con| {
-            lines::table
-                .select(DbLineComp::as_select())
-                .filter(lines::song_id.eq(table_id_2))
-                .order(lines::id.asc())
-                .load(con)
+            let song_lines = load_song_lines(con, table_id_2)?;
+            let sections = load_sections(con, table_id_2, &song_lines)?;
+            let vamps = load_vamps(con, table_id_2, &song_lines)?;
+            let style = load_song_style(con, table_id_2)?;
+            let characters = load_characters(con, &song_lines)?;
+
+            QueryResult::Ok((song_lines, sections, vamps, style, characters))
         })
         .await
         .unwrap()
-        .unwrap()
-        .into_iter()
-        .map(LineComp::from)
-        .collect::<Vec<_>>();
+        .unwrap();
 
     let load_song = LoadSong {
         id: 0,
         title: "Don't care".to_string(),
         lines: lines_res,
+        sections,
+        vamps,
+        style,
+        characters,
     };
-    let _ = state.load_song_ch.send(load_song);
+    let _ = state.load_song_ch.send(load_song.for_audience());
+    let _ = state.operator_ch.send(operator_cue(&active_song, &[], &[]));
+    show_frame(&state, song_frame(&active_song, &[], &LineStyle::default())).await;
+
+    save_cue_state(&pool, *active_song).await;
+
+    record_cue(
+        &pool,
+        NewCueLogEntry {
+            event: CueEvent::SetSong,
+            source: CueSource::Http,
+            operator,
+            song_id: Some(song_req.id),
+            song_name: Some(name),
+            line_index: None,
+            line: None,
+            skips: None,
+        },
+    )
+    .await;
 
     StatusCode::OK
 }
@@ -198,68 +325,256 @@ pub struct SkipLineRequest {
 
 pub async fn next_line(
     State(state): State<Store>,
+    Operator(operator): Operator,
     Json(skip): Json<SkipLineRequest>,
 ) -> StatusCode {
     let pool = state.pool.get().await.unwrap();
 
     let mut active_song = state.active_song.write().await;
 
-    use super::schema::lines::dsl::*;
-
     let song_id_comp = active_song.id;
 
-    let song_lines = pool
+    let (name, song_lines, vamps, style) = pool
         .interact(move |con| {
-            lines
-                .filter(song_id.eq(song_id_comp))
-                .select(line)
-                .order(id.asc())
-                .load::<String>(con)
+            let name = song_name(con, song_id_comp)?;
+            let song_lines = load_song_lines(con, song_id_comp)?;
+            let vamps = load_vamps(con, song_id_comp, &song_lines)?;
+            let style = load_song_style(con, song_id_comp)?;
+
+            QueryResult::Ok((name, song_lines, vamps, style))
         })
         .await
         .unwrap()
         .unwrap();
 
-    active_song.line = if skip.skips >= 0 {
-        active_song.line + skip.skips as u32
+    let target = if skip.skips >= 0 {
+        wrap_target(&vamps, &active_song, active_song.line + skip.skips as u32)
     } else {
         active_song.line.saturating_sub(skip.skips.unsigned_abs())
     };
 
-    if active_song.line >= song_lines.len() as u32 {
-        active_song.line = song_lines.len() as u32;
-    }
+    let line_comp = move_cursor(
+        &state,
+        &mut active_song,
+        &song_lines,
+        &vamps,
+        &style,
+        target,
+    )
+    .await;
+
+    save_cue_state(&pool, *active_song).await;
+
+    record_cue(
+        &pool,
+        NewCueLogEntry {
+            event: CueEvent::Line,
+            source: CueSource::Http,
+            operator,
+            song_id: name.is_some().then_some(song_id_comp),
+            song_name: name,
+            line_index: Some(active_song.line as i32),
+            line: Some(line_comp),
+            skips: Some(skip.skips),
+        },
+    )
+    .await;
 
-    if active_song.line == 0 {
-        let _ = state.line_ch.send("".to_string());
-        let _ = state.index_ch.send(None);
-        return StatusCode::OK;
-    } else {
-        let _ = state.index_ch.send(Some(active_song.line));
+    StatusCode::OK
+}
+
+/// Moves the cursor of the active song to `target`, clamped to the end of the
+/// song, and broadcasts the result. Returns the text now shown on the displays.
+async fn move_cursor(
+    state: &Store,
+    active_song: &mut ActiveSong,
+    song_lines: &[LineComp],
+    vamps: &[Vamp],
+    style: &LineStyle,
+    target: u32,
+) -> String {
+    let previous = shown_index(active_song.line, song_lines);
+
+    active_song.line = target.min(song_lines.len() as u32);
+    leave_vamps(vamps, active_song);
+
+    let cued_at = Utc::now();
+    active_song.cued_at = Some(cued_at);
+
+    let _ = state
+        .operator_ch
+        .send(operator_cue(active_song, song_lines, vamps));
+
+    // Cueing a stage direction leaves the displays on the line before it
+    let index = shown_index(active_song.line, song_lines);
+    if index == Some(active_song.line) || index != previous {
+        let _ = state.index_ch.send(index);
+        let _ = state.cue_ch.send(Cue {
+            index,
+            started_at: cued_at,
+        });
+        show_frame(state, song_frame(active_song, song_lines, style)).await;
     }
 
-    let line_comp = song_lines[active_song.line as usize - 1].clone();
+    // Lines that are never broadcast leave the previous line on the displays
+    let line_comp = song_lines[..active_song.line as usize]
+        .iter()
+        .rev()
+        .find_map(|line_comp| line_comp.audience_text())
+        .unwrap_or_default();
+
+    show_song_line(state, line_comp.clone()).await;
+
+    line_comp
+}
 
-    if line_comp == "---" {
-        let _ = state.line_ch.send("".to_string());
-        return StatusCode::OK;
+/// What the operator sees of the cursor of the active song.
+pub fn operator_cue(
+    active_song: &ActiveSong,
+    song_lines: &[LineComp],
+    vamps: &[Vamp],
+) -> OperatorCue {
+    let index = Some(active_song.line).filter(|&index| index > 0);
+
+    OperatorCue {
+        song_id: active_song.id,
+        index,
+        line: index.and_then(|index| song_lines.get(index as usize - 1).cloned()),
+        vamp: active_vamp(vamps, active_song),
+        started_at: active_song.cued_at,
     }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum SectionRef {
+    Index(usize),
+    Name(String),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct JumpRequest {
+    section: SectionRef,
+}
+
+pub async fn jump_to_section(
+    State(state): State<Store>,
+    Operator(operator): Operator,
+    Json(jump): Json<JumpRequest>,
+) -> StatusCode {
+    let pool = state.pool.get().await.unwrap();
+
+    let mut active_song = state.active_song.write().await;
+
+    let song_id_comp = active_song.id;
+
+    let (name, song_lines, sections, vamps, style) = pool
+        .interact(move |con| {
+            let name = song_name(con, song_id_comp)?;
+            let song_lines = load_song_lines(con, song_id_comp)?;
+            let sections = load_sections(con, song_id_comp, &song_lines)?;
+            let vamps = load_vamps(con, song_id_comp, &song_lines)?;
+            let style = load_song_style(con, song_id_comp)?;
+
+            QueryResult::Ok((name, song_lines, sections, vamps, style))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let section = match &jump.section {
+        SectionRef::Index(index) => sections.get(*index),
+        SectionRef::Name(section_name) => sections
+            .iter()
+            .find(|section| section.name.eq_ignore_ascii_case(section_name)),
+    };
 
-    let _ = state.line_ch.send(line_comp);
+    let Some(section) = section else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    info!("Jumping to section: {}", section.name);
+
+    let previous = active_song.line;
+    let line_comp = move_cursor(
+        &state,
+        &mut active_song,
+        &song_lines,
+        &vamps,
+        &style,
+        section.index + 1,
+    )
+    .await;
+
+    save_cue_state(&pool, *active_song).await;
+
+    record_cue(
+        &pool,
+        NewCueLogEntry {
+            event: CueEvent::Line,
+            source: CueSource::Http,
+            operator,
+            song_id: name.is_some().then_some(song_id_comp),
+            song_name: name,
+            line_index: Some(active_song.line as i32),
+            line: Some(line_comp),
+            skips: Some(active_song.line as i32 - previous as i32),
+        },
+    )
+    .await;
 
     StatusCode::OK
 }
 
-pub async fn reset_line(State(state): State<Store>) -> StatusCode {
-    let _ = state.line_ch.send("".to_string());
+pub async fn reset_line(State(state): State<Store>, Operator(operator): Operator) -> StatusCode {
+    show_song_line(&state, "".to_string()).await;
     let _ = state.index_ch.send(None);
 
-    state.active_song.write().await.line = 0;
+    let mut active_song = state.active_song.write().await;
+    active_song.line = 0;
+    active_song.broken_vamp = None;
+    active_song.cued_at = None;
+
+    let _ = state.operator_ch.send(operator_cue(&active_song, &[], &[]));
+    show_frame(&state, song_frame(&active_song, &[], &LineStyle::default())).await;
+
+    let pool = state.pool.get().await.unwrap();
+
+    save_cue_state(&pool, *active_song).await;
+
+    let active_id = active_song.id;
+    let name = pool
+        .interact(move |con| song_name(con, active_id))
+        .await
+        .unwrap()
+        .unwrap_or_default();
+
+    record_cue(
+        &pool,
+        NewCueLogEntry {
+            event: CueEvent::Reset,
+            source: CueSource::Http,
+            operator,
+            song_id: name.is_some().then_some(active_id),
+            song_name: name,
+            line_index: None,
+            line: None,
+            skips: None,
+        },
+    )
+    .await;
 
     StatusCode::OK
 }
 
-pub async fn edit_song(State(store): State<Store>, Json(body): Json<LineComp>) -> StatusCode {
+/// Saves a line from the editor, which only sends its text, placement and camera.
+/// Everything else about a line is changed with `patch::patch_line`.
+pub async fn edit_song(
+    State(store): State<Store>,
+    Json(body): Json<LineComp>,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    parse_markup(&body.line).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     let pool = store.pool.get().await.unwrap();
 
     use super::schema::lines::dsl::*;
@@ -268,7 +583,7 @@ pub async fn edit_song(State(store): State<Store>, Json(body): Json<LineComp>) -
         .interact(move |con| {
             diesel::update(lines.filter(id.eq(body.id)))
                 .set((
-                    line.eq(body.line),
+                    line.eq(sanitize(&body.line)),
                     position.eq::<Vector>(body.position.into()),
                     cam_position.eq::<Vector>(body.cam_position.into()),
                     cam_look_at.eq::<Vector>(body.cam_look_at.into()),
@@ -287,12 +602,12 @@ pub async fn edit_song(State(store): State<Store>, Json(body): Json<LineComp>) -
 
     if res.is_err() {
         error!("Failed to update song with id: {}", body.id);
-        return StatusCode::INTERNAL_SERVER_ERROR;
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to update line"));
     }
 
     info!("Updated song with id: {}", body.id);
 
-    StatusCode::OK
+    Ok(StatusCode::OK)
 }
 
 #[derive(Deserialize)]
@@ -323,24 +638,105 @@ pub async fn delete_line(State(store): State<Store>, Json(body): Json<DeleteLine
     StatusCode::OK
 }
 
+/// Loads a single line with everything `LineComp` carries, as it is stored.
+///
+/// Presets aren't applied, so editors see and save the line's own values.
+pub fn load_line(con: &mut PgConnection, line_id: i32) -> QueryResult<LineComp> {
+    let mut line_comp = lines::table
+        .select(DbLineComp::as_select())
+        .filter(lines::id.eq(line_id))
+        .first(con)
+        .map(LineComp::from)?;
+
+    load_timings(con, std::slice::from_mut(&mut line_comp))?;
+
+    Ok(line_comp)
+}
+
 pub async fn get_line(
     State(store): State<Store>,
     Query(body): Query<DeleteLine>,
 ) -> Result<Json<LineComp>, (StatusCode, &'static str)> {
     let pool = store.pool.get().await.unwrap();
 
+    let res = pool
+        .interact(move |con| load_line(con, body.id))
+        .await
+        .unwrap();
+
+    match res {
+        Ok(val) => Ok(Json(val)),
+        Err(_) => Err((StatusCode::NOT_FOUND, "Failed to find line")),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NewSection {
+    song_id: i32,
+    name: String,
+    line_id: i32,
+    /// Which time the song plays the line, for lines of blocks played more than once.
+    #[serde(default)]
+    occurrence: i32,
+}
+
+pub async fn add_section(
+    State(store): State<Store>,
+    Json(body): Json<NewSection>,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    let pool = store.pool.get().await.unwrap();
+
     let res = pool
         .interact(move |con| {
-            lines::table
-                .select(DbLineComp::as_select())
-                .filter(lines::id.eq(body.id))
-                .first(con)
+            let song_lines = load_song_lines(con, body.song_id)?;
+
+            if play_position(&song_lines, body.line_id, body.occurrence).is_none() {
+                return QueryResult::Ok(false);
+            }
+
+            diesel::insert_into(section::table)
+                .values((
+                    section::song_id.eq(body.song_id),
+                    section::name.eq(body.name.trim()),
+                    section::line_id.eq(body.line_id),
+                    section::occurrence.eq(body.occurrence),
+                ))
+                .execute(con)?;
+
+            QueryResult::Ok(true)
         })
         .await
         .unwrap();
 
     match res {
-        Ok(val) => Ok(Json(LineComp::from(val))),
-        Err(_) => Err((StatusCode::NOT_FOUND, "Failed to find line")),
+        Ok(true) => Ok(StatusCode::CREATED),
+        Ok(false) => Err((
+            StatusCode::BAD_REQUEST,
+            "Song does not play the line that many times",
+        )),
+        Err(e) => {
+            error!("Failed to add section: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to add section"))
+        }
+    }
+}
+
+pub async fn delete_section(
+    State(store): State<Store>,
+    Json(body): Json<DeleteLine>,
+) -> StatusCode {
+    let pool = store.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| diesel::delete(section::table.find(body.id)).execute(con))
+        .await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        error!("Failed to delete section with id: {}", body.id);
+        return StatusCode::INTERNAL_SERVER_ERROR;
     }
+
+    info!("Deleted section with id: {}", body.id);
+
+    StatusCode::OK
 }
@@ -0,0 +1,233 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    cue_log::{record_override, CueEvent, Operator},
+    output::{publish, Output},
+    style::valid_color,
+    types::{Countdown, CountdownMode},
+    Store,
+};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+const OUT_OF_RANGE: (StatusCode, &str) = (StatusCode::BAD_REQUEST, "Countdown time out of range");
+
+const UNCHANGEABLE: (StatusCode, &str) =
+    (StatusCode::CONFLICT, "Countdown can't be changed like that");
+
+/// The time `delta` from now, `None` when it isn't a time chrono can represent.
+fn from_now(delta: Option<TimeDelta>) -> Option<DateTime<Utc>> {
+    Utc::now().checked_add_signed(delta?)
+}
+
+/// Replaces the countdown and sends the change to the displays.
+fn show_countdown(state: &Store, output: &mut Output, countdown: Option<Countdown>) {
+    output.countdown = countdown.clone();
+
+    // An announcement keeps the displays until it clears
+    if output.announcement.is_none() {
+        publish(state, output);
+    }
+
+    let _ = state.countdown_ch.send(countdown.clone());
+
+    if let Some(Countdown {
+        id,
+        mode: CountdownMode::Countdown,
+        target: Some(target),
+        ..
+    }) = countdown
+    {
+        schedule_end(state.clone(), id, target);
+    }
+}
+
+/// Returns to the setlist once the countdown reaches zero.
+fn schedule_end(state: Store, id: u64, target: DateTime<Utc>) {
+    tokio::spawn(async move {
+        let wait = (target - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+
+        let mut output = state.output.write().await;
+
+        // Pausing, extending or replacing the countdown leaves this timer behind
+        if output
+            .countdown
+            .as_ref()
+            .is_some_and(|countdown| countdown.id == id && countdown.target == Some(target))
+        {
+            info!("Countdown finished, returning to the setlist");
+            show_countdown(&state, &mut output, None);
+            drop(output);
+
+            record_override(&state, CueEvent::ClearCountdown, None, None).await;
+        }
+    });
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewCountdown {
+    #[serde(default)]
+    mode: CountdownMode,
+    duration_secs: Option<u64>,
+    target: Option<DateTime<Utc>>,
+    label: Option<String>,
+    color: Option<String>,
+}
+
+pub async fn start_countdown(
+    State(state): State<Store>,
+    Operator(operator): Operator,
+    Json(body): Json<NewCountdown>,
+) -> Result<Json<Countdown>, (StatusCode, &'static str)> {
+    if body
+        .color
+        .as_deref()
+        .is_some_and(|color| !valid_color(color))
+    {
+        return Err((StatusCode::BAD_REQUEST, "Invalid countdown color"));
+    }
+
+    let target = match body.mode {
+        CountdownMode::Clock => None,
+        CountdownMode::Countdown => {
+            let target = match (body.target, body.duration_secs) {
+                (Some(target), _) => target,
+                (None, Some(secs)) => {
+                    from_now(i64::try_from(secs).ok().and_then(TimeDelta::try_seconds))
+                        .ok_or(OUT_OF_RANGE)?
+                }
+                (None, None) => {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        "A countdown needs a duration or a target time",
+                    ))
+                }
+            };
+
+            if target <= Utc::now() {
+                return Err((StatusCode::BAD_REQUEST, "Target time has already passed"));
+            }
+
+            Some(target)
+        }
+    };
+
+    let countdown = Countdown {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        mode: body.mode,
+        label: body.label.map(|label| label.trim().to_string()),
+        color: body.color,
+        target,
+        remaining_ms: None,
+    };
+
+    info!("Starting countdown: {:?}", countdown);
+
+    let mut output = state.output.write().await;
+    show_countdown(&state, &mut output, Some(countdown.clone()));
+    drop(output);
+
+    // Logged with an empty text when there is no label, the countdown still hides the song
+    let label = countdown.label.clone().unwrap_or_default();
+    record_override(&state, CueEvent::Countdown, Some(label), operator).await;
+
+    Ok(Json(countdown))
+}
+
+/// Applies `change` to the running countdown, which fails if it doesn't apply.
+async fn change_countdown(
+    state: &Store,
+    change: impl FnOnce(&mut Countdown) -> Result<(), (StatusCode, &'static str)>,
+) -> Result<Json<Countdown>, (StatusCode, &'static str)> {
+    let mut output = state.output.write().await;
+
+    let Some(mut countdown) = output.countdown.clone() else {
+        return Err((StatusCode::NOT_FOUND, "No countdown is running"));
+    };
+
+    if countdown.mode != CountdownMode::Countdown {
+        return Err(UNCHANGEABLE);
+    }
+    change(&mut countdown)?;
+
+    show_countdown(state, &mut output, Some(countdown.clone()));
+
+    Ok(Json(countdown))
+}
+
+pub async fn pause_countdown(
+    State(state): State<Store>,
+) -> Result<Json<Countdown>, (StatusCode, &'static str)> {
+    change_countdown(&state, |countdown| {
+        let target = countdown.target.take().ok_or(UNCHANGEABLE)?;
+
+        countdown.remaining_ms = Some((target - Utc::now()).num_milliseconds().max(0));
+        Ok(())
+    })
+    .await
+}
+
+pub async fn resume_countdown(
+    State(state): State<Store>,
+) -> Result<Json<Countdown>, (StatusCode, &'static str)> {
+    change_countdown(&state, |countdown| {
+        let remaining_ms = countdown.remaining_ms.take().ok_or(UNCHANGEABLE)?;
+
+        countdown.target =
+            Some(from_now(TimeDelta::try_milliseconds(remaining_ms)).ok_or(OUT_OF_RANGE)?);
+        Ok(())
+    })
+    .await
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExtendCountdown {
+    /// Seconds to add, negative to shorten the countdown.
+    secs: i64,
+}
+
+pub async fn extend_countdown(
+    State(state): State<Store>,
+    Json(body): Json<ExtendCountdown>,
+) -> Result<Json<Countdown>, (StatusCode, &'static str)> {
+    let extra = TimeDelta::try_seconds(body.secs).ok_or(OUT_OF_RANGE)?;
+
+    change_countdown(&state, |countdown| {
+        if let Some(target) = &mut countdown.target {
+            *target = target.checked_add_signed(extra).ok_or(OUT_OF_RANGE)?;
+        }
+        if let Some(remaining_ms) = &mut countdown.remaining_ms {
+            *remaining_ms = remaining_ms
+                .checked_add(extra.num_milliseconds())
+                .ok_or(OUT_OF_RANGE)?
+                .max(0);
+        }
+        Ok(())
+    })
+    .await
+}
+
+pub async fn cancel_countdown(
+    State(state): State<Store>,
+    Operator(operator): Operator,
+) -> StatusCode {
+    let mut output = state.output.write().await;
+
+    if output.countdown.is_none() {
+        return StatusCode::NOT_FOUND;
+    }
+
+    info!("Cancelling countdown");
+    show_countdown(&state, &mut output, None);
+    drop(output);
+
+    record_override(&state, CueEvent::ClearCountdown, None, operator).await;
+
+    StatusCode::OK
+}
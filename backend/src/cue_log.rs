@@ -0,0 +1,380 @@
+use std::{convert::Infallible, fmt::Write};
+
+use axum::{
+    extract::{FromRequestParts, Query, State},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use deadpool_diesel::postgres::Object;
+use diesel::{
+    prelude::{Insertable, Queryable},
+    ExpressionMethods, QueryDsl, RunQueryDsl, Selectable, SelectableHelper,
+};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{controller::song_name, schema::cue_log, Store};
+
+/// Header used by operator clients to identify themselves in the cue log.
+const OPERATOR_HEADER: &str = "x-operator";
+
+/// Gaps between two cues of the same song longer than this are reported.
+const DEFAULT_GAP_SECS: i64 = 60;
+
+#[derive(Debug, diesel_derive_enum::DbEnum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::CueEvent"]
+pub enum CueEvent {
+    SetSong,
+    Line,
+    Reset,
+    Announce,
+    ClearAnnouncement,
+    Countdown,
+    ClearCountdown,
+}
+
+#[derive(Debug, diesel_derive_enum::DbEnum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::CueSource"]
+pub enum CueSource {
+    Http,
+    Osc,
+    Ws,
+}
+
+/// The operator who triggered a cue, taken from the `X-Operator` header.
+pub struct Operator(pub Option<String>);
+
+impl<S: Send + Sync> FromRequestParts<S> for Operator {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let operator = parts
+            .headers
+            .get(OPERATOR_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+
+        Ok(Operator(operator))
+    }
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = cue_log)]
+pub struct NewCueLogEntry {
+    pub event: CueEvent,
+    pub source: CueSource,
+    pub operator: Option<String>,
+    pub song_id: Option<i32>,
+    pub song_name: Option<String>,
+    pub line_index: Option<i32>,
+    pub line: Option<String>,
+    pub skips: Option<i32>,
+}
+
+#[derive(Debug, Queryable, Selectable, Serialize)]
+#[diesel(table_name = cue_log)]
+pub struct CueLogEntry {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub event: CueEvent,
+    pub source: CueSource,
+    pub operator: Option<String>,
+    pub song_id: Option<i32>,
+    pub song_name: Option<String>,
+    pub line_index: Option<i32>,
+    pub line: Option<String>,
+    pub skips: Option<i32>,
+}
+
+/// Writes a cue to the log. Failures are logged but never fail the cue itself.
+pub async fn record_cue(pool: &Object, entry: NewCueLogEntry) {
+    let res = pool
+        .interact(move |con| {
+            diesel::insert_into(cue_log::table)
+                .values(&entry)
+                .execute(con)
+        })
+        .await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        error!("Failed to write cue log entry: {:?}", res);
+    }
+}
+
+/// Writes an announcement or countdown to the log, against the active song.
+pub async fn record_override(
+    state: &Store,
+    event: CueEvent,
+    text: Option<String>,
+    operator: Option<String>,
+) {
+    let pool = state.pool.get().await.unwrap();
+
+    let active_id = state.active_song.read().await.id;
+    let name = pool
+        .interact(move |con| song_name(con, active_id))
+        .await
+        .unwrap()
+        .unwrap_or_default();
+
+    record_cue(
+        &pool,
+        NewCueLogEntry {
+            event,
+            source: CueSource::Http,
+            operator,
+            song_id: name.is_some().then_some(active_id),
+            song_name: name,
+            line_index: None,
+            line: text,
+            skips: None,
+        },
+    )
+    .await;
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CueLogRequest {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    gap_secs: Option<i64>,
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Gap {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub secs: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SongReport {
+    pub song_id: Option<i32>,
+    pub song_name: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub duration_secs: i64,
+    pub cues: u32,
+    pub back_skips: u32,
+    pub resets: u32,
+    pub announcements: u32,
+    pub gaps: Vec<Gap>,
+}
+
+impl SongReport {
+    fn new(entry: &CueLogEntry) -> Self {
+        SongReport {
+            song_id: entry.song_id,
+            song_name: entry.song_name.clone(),
+            started_at: entry.created_at,
+            ended_at: entry.created_at,
+            duration_secs: 0,
+            cues: 0,
+            back_skips: 0,
+            resets: 0,
+            announcements: 0,
+            gaps: Vec::new(),
+        }
+    }
+}
+
+/// Loads the log entries between `from` and `to` (both inclusive) in the order they happened.
+pub async fn load_entries(
+    pool: &Object,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Vec<CueLogEntry>, (StatusCode, &'static str)> {
+    let res = pool
+        .interact(move |con| {
+            let mut query = cue_log::table
+                .select(CueLogEntry::as_select())
+                .order((cue_log::created_at.asc(), cue_log::id.asc()))
+                .into_boxed();
+
+            if let Some(from) = from {
+                query = query.filter(cue_log::created_at.ge(from));
+            }
+            if let Some(to) = to {
+                query = query.filter(cue_log::created_at.le(to));
+            }
+
+            query.load(con)
+        })
+        .await
+        .unwrap();
+
+    res.map_err(|e| {
+        error!("Failed to load cue log: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load cue log")
+    })
+}
+
+/// Splits the log into one segment per played song and summarises each of them.
+///
+/// A new segment starts whenever a song is set, or a cue belongs to another
+/// song than the previous one, so a song played twice shows up twice.
+pub fn build_report(entries: &[CueLogEntry], gap_threshold: Duration) -> Vec<SongReport> {
+    let mut songs: Vec<SongReport> = Vec::new();
+
+    for entry in entries {
+        let new_segment = match songs.last() {
+            None => true,
+            Some(current) => entry.event == CueEvent::SetSong || current.song_id != entry.song_id,
+        };
+
+        if new_segment {
+            songs.push(SongReport::new(entry));
+        }
+
+        let current = songs.last_mut().unwrap();
+
+        let since_last = entry.created_at - current.ended_at;
+        if since_last > gap_threshold {
+            current.gaps.push(Gap {
+                from: current.ended_at,
+                to: entry.created_at,
+                secs: since_last.num_seconds(),
+            });
+        }
+
+        match entry.event {
+            CueEvent::Line => {
+                current.cues += 1;
+                if entry.skips.is_some_and(|skips| skips < 0) {
+                    current.back_skips += 1;
+                }
+            }
+            CueEvent::Reset => current.resets += 1,
+            CueEvent::Announce => current.announcements += 1,
+            CueEvent::SetSong
+            | CueEvent::ClearAnnouncement
+            | CueEvent::Countdown
+            | CueEvent::ClearCountdown => {}
+        }
+
+        current.ended_at = entry.created_at;
+        current.duration_secs = (current.ended_at - current.started_at).num_seconds();
+    }
+
+    songs
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_opt<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(|v| csv_field(&v.to_string()))
+        .unwrap_or_default()
+}
+
+fn csv_response(filename: &str, body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+pub async fn get_cue_log(
+    State(state): State<Store>,
+    Query(req): Query<CueLogRequest>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let entries = load_entries(&pool, req.from, req.to).await?;
+
+    match req.format {
+        ExportFormat::Json => Ok(Json(entries).into_response()),
+        ExportFormat::Csv => {
+            let mut body =
+                "id,created_at,event,source,operator,song_id,song_name,line_index,line,skips\n"
+                    .to_string();
+
+            for entry in &entries {
+                let _ = writeln!(
+                    body,
+                    "{},{},{:?},{:?},{},{},{},{},{},{}",
+                    entry.id,
+                    entry.created_at.to_rfc3339(),
+                    entry.event,
+                    entry.source,
+                    csv_opt(&entry.operator),
+                    csv_opt(&entry.song_id),
+                    csv_opt(&entry.song_name),
+                    csv_opt(&entry.line_index),
+                    csv_opt(&entry.line),
+                    csv_opt(&entry.skips),
+                );
+            }
+
+            Ok(csv_response("cue_log.csv", body))
+        }
+    }
+}
+
+pub async fn get_cue_report(
+    State(state): State<Store>,
+    Query(req): Query<CueLogRequest>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    let gap_threshold = Duration::try_seconds(req.gap_secs.unwrap_or(DEFAULT_GAP_SECS))
+        .ok_or((StatusCode::BAD_REQUEST, "gap_secs out of range"))?;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let entries = load_entries(&pool, req.from, req.to).await?;
+
+    let report = build_report(&entries, gap_threshold);
+
+    match req.format {
+        ExportFormat::Json => Ok(Json(report).into_response()),
+        ExportFormat::Csv => {
+            let mut body = "song_id,song_name,started_at,ended_at,duration_secs,cues,back_skips,resets,announcements,gaps,longest_gap_secs\n".to_string();
+
+            for song in &report {
+                let _ = writeln!(
+                    body,
+                    "{},{},{},{},{},{},{},{},{},{},{}",
+                    csv_opt(&song.song_id),
+                    csv_opt(&song.song_name),
+                    song.started_at.to_rfc3339(),
+                    song.ended_at.to_rfc3339(),
+                    song.duration_secs,
+                    song.cues,
+                    song.back_skips,
+                    song.resets,
+                    song.announcements,
+                    song.gaps.len(),
+                    song.gaps.iter().map(|gap| gap.secs).max().unwrap_or(0),
+                );
+            }
+
+            Ok(csv_response("cue_report.csv", body))
+        }
+    }
+}
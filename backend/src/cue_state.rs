@@ -0,0 +1,79 @@
+use deadpool_diesel::postgres::{Object, Pool};
+use diesel::{
+    prelude::{AsChangeset, Insertable, Queryable},
+    OptionalExtension, QueryDsl, RunQueryDsl, Selectable, SelectableHelper,
+};
+use tracing::{error, info};
+
+use crate::{schema::cue_state, ActiveSong};
+
+/// The table only ever holds this one row.
+const CUE_STATE_ID: i32 = 1;
+
+#[derive(Debug, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = cue_state)]
+struct DbCueState {
+    id: i32,
+    song_id: i32,
+    line: i32,
+    broken_vamp_id: Option<i32>,
+}
+
+/// Persists the cursor of the active song. Failures are logged but never fail the cue itself.
+pub async fn save_cue_state(pool: &Object, active_song: ActiveSong) {
+    let row = DbCueState {
+        id: CUE_STATE_ID,
+        song_id: active_song.id,
+        line: active_song.line as i32,
+        broken_vamp_id: active_song.broken_vamp,
+    };
+
+    let res = pool
+        .interact(move |con| {
+            diesel::insert_into(cue_state::table)
+                .values(&row)
+                .on_conflict(cue_state::id)
+                .do_update()
+                .set(&row)
+                .execute(con)
+        })
+        .await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        error!("Failed to save cue state: {:?}", res);
+    }
+}
+
+/// Restores the cursor saved before the server was last stopped.
+pub async fn load_cue_state(pool: &Pool) -> ActiveSong {
+    let con = pool.get().await.unwrap();
+
+    let res = con
+        .interact(|con| {
+            cue_state::table
+                .find(CUE_STATE_ID)
+                .select(DbCueState::as_select())
+                .first(con)
+                .optional()
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(Some(row)) => {
+            info!("Restored cue state: song {} line {}", row.song_id, row.line);
+
+            ActiveSong {
+                id: row.song_id,
+                line: row.line.max(0) as u32,
+                broken_vamp: row.broken_vamp_id,
+                cued_at: None,
+            }
+        }
+        Ok(None) => ActiveSong::default(),
+        Err(e) => {
+            error!("Failed to load cue state: {}", e);
+            ActiveSong::default()
+        }
+    }
+}
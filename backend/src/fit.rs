@@ -0,0 +1,575 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, QueryResult, RunQueryDsl,
+    SelectableHelper,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+use ts_rs::TS;
+
+use crate::{
+    markup::{parse_markup, to_markup},
+    schema::{output_rule, song},
+    structure::load_song_lines,
+    types::{LineKind, OutputRule, Span},
+    Store,
+};
+
+/// Styled fragments of a word, split where the styling changes.
+type Word = Vec<Span>;
+
+fn len(spans: &[Span]) -> usize {
+    spans
+        .iter()
+        .map(|span| match span {
+            Span::Text { text, .. } => text.chars().count(),
+            Span::Break => 0,
+        })
+        .sum()
+}
+
+/// Width of words on a row, one space between each.
+fn row_len(words: &[Word]) -> usize {
+    words.iter().map(|word| len(word)).sum::<usize>() + words.len().saturating_sub(1)
+}
+
+/// Adds `c` to the word, in a new fragment when the styling changes.
+fn push_char(word: &mut Word, c: char, style: (bool, bool, bool)) {
+    if let Some(Span::Text {
+        text,
+        bold,
+        italic,
+        emphasis,
+    }) = word.last_mut()
+    {
+        if (*bold, *italic, *emphasis) == style {
+            text.push(c);
+            return;
+        }
+    }
+
+    word.push(Span::Text {
+        text: c.to_string(),
+        bold: style.0,
+        italic: style.1,
+        emphasis: style.2,
+    });
+}
+
+/// Splits a row into words at whitespace, keeping the styling of every character.
+fn words(row: &[Span]) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut word = Word::new();
+
+    for span in row {
+        let Span::Text {
+            text,
+            bold,
+            italic,
+            emphasis,
+        } = span
+        else {
+            continue;
+        };
+
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            } else {
+                push_char(&mut word, c, (*bold, *italic, *emphasis));
+            }
+        }
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Cuts a word into pieces of at most `max_chars` characters.
+fn cut_word(word: Word, max_chars: usize) -> Vec<Word> {
+    if len(&word) <= max_chars {
+        return vec![word];
+    }
+
+    let mut pieces = vec![Word::new()];
+    for span in word {
+        let Span::Text {
+            text,
+            bold,
+            italic,
+            emphasis,
+        } = span
+        else {
+            continue;
+        };
+
+        for c in text.chars() {
+            if len(pieces.last().unwrap()) == max_chars {
+                pieces.push(Word::new());
+            }
+            push_char(pieces.last_mut().unwrap(), c, (bold, italic, emphasis));
+        }
+    }
+
+    pieces
+}
+
+/// Whether a row may end after the word, at a comma, full stop and the like.
+fn natural_end(word: &Word) -> bool {
+    let last = word.iter().rev().find_map(|span| match span {
+        Span::Text { text, .. } => text.chars().last(),
+        Span::Break => None,
+    });
+
+    matches!(
+        last,
+        Some(',' | ';' | ':' | '.' | '!' | '?' | ')' | '-' | '–' | '—')
+    )
+}
+
+/// Wraps words into rows of at most `max_chars` characters.
+///
+/// A row ends after punctuation rather than at the last word that fits, as
+/// long as the row stays at least half full.
+fn wrap(words: Vec<Word>, max_chars: usize) -> Vec<Vec<Word>> {
+    let mut rows = Vec::new();
+    let mut row: Vec<Word> = Vec::new();
+
+    for word in words.into_iter().flat_map(|word| cut_word(word, max_chars)) {
+        if !row.is_empty() && row_len(&row) + 1 + len(&word) > max_chars {
+            let natural = (1..row.len())
+                .rev()
+                .find(|&i| natural_end(&row[i - 1]) && row_len(&row[..i]) * 2 >= max_chars);
+
+            match natural {
+                Some(i) => {
+                    let rest = row.split_off(i);
+                    rows.push(std::mem::replace(&mut row, rest));
+                }
+                None => rows.push(std::mem::take(&mut row)),
+            }
+
+            // What moved down may not leave room for the word either
+            if !row.is_empty() && row_len(&row) + 1 + len(&word) > max_chars {
+                rows.push(std::mem::take(&mut row));
+            }
+        }
+
+        row.push(word);
+    }
+
+    if !row.is_empty() {
+        rows.push(row);
+    }
+
+    rows
+}
+
+fn style(span: &Span) -> Option<(bool, bool, bool)> {
+    match span {
+        Span::Text {
+            bold,
+            italic,
+            emphasis,
+            ..
+        } => Some((*bold, *italic, *emphasis)),
+        Span::Break => None,
+    }
+}
+
+/// Adds a span to a row, joined with the one before it when styled the same.
+fn push_span(spans: &mut Vec<Span>, span: Span) {
+    if let (Some(Span::Text { text, .. }), Span::Text { text: more, .. }) = (
+        spans.last_mut().filter(|last| style(last) == style(&span)),
+        &span,
+    ) {
+        text.push_str(more);
+        return;
+    }
+
+    spans.push(span);
+}
+
+/// Joins the words of a row back into spans.
+fn row_spans(row: Vec<Word>) -> Vec<Span> {
+    let mut spans = Vec::new();
+
+    for word in row {
+        if let (Some(last), Some(first)) = (spans.last(), word.first()) {
+            // The space is only styled between words styled the same
+            let (bold, italic, emphasis) = match style(last) == style(first) {
+                true => style(first).unwrap_or_default(),
+                false => Default::default(),
+            };
+            push_span(
+                &mut spans,
+                Span::Text {
+                    text: " ".to_string(),
+                    bold,
+                    italic,
+                    emphasis,
+                },
+            );
+        }
+
+        for span in word {
+            push_span(&mut spans, span);
+        }
+    }
+
+    spans
+}
+
+/// Rows of a line as written and the length of the longest one.
+pub fn measure(spans: &[Span]) -> (usize, usize) {
+    let rows = spans.split(|span| matches!(span, Span::Break));
+    let lengths = rows.map(len).collect::<Vec<_>>();
+
+    (lengths.len(), lengths.into_iter().max().unwrap_or(0))
+}
+
+/// Wraps a line into rows of at most `max_chars` characters, keeping its breaks.
+pub fn wrap_spans(spans: &[Span], max_chars: usize) -> Vec<Vec<Span>> {
+    spans
+        .split(|span| matches!(span, Span::Break))
+        .flat_map(|row| wrap(words(row), max_chars.max(1)))
+        .map(row_spans)
+        .collect()
+}
+
+/// Proposes how to show a line within `max_chars` characters and `max_rows` rows.
+///
+/// Returns the line as markup split into as many cues as it needs, rows broken
+/// with `//`. Cues share the rows evenly, and existing breaks are kept.
+pub fn split_line(
+    source: &str,
+    max_chars: usize,
+    max_rows: usize,
+) -> Result<Vec<String>, &'static str> {
+    let spans = parse_markup(source)?;
+    let max_rows = max_rows.max(1);
+
+    let rows = wrap_spans(&spans, max_chars);
+
+    if rows.is_empty() {
+        return Ok(vec![source.to_string()]);
+    }
+
+    let cues = rows.len().div_ceil(max_rows);
+    let per_cue = rows.len() / cues;
+    let longer = rows.len() % cues;
+
+    let mut rows = rows.into_iter();
+    let split = (0..cues)
+        .map(|cue| {
+            let cue_rows = (&mut rows)
+                .take(per_cue + usize::from(cue < longer))
+                .collect::<Vec<_>>();
+            let spans = cue_rows.join(&Span::Break);
+            to_markup(&spans)
+        })
+        .collect();
+
+    Ok(split)
+}
+
+/// Replaces imported lines too long for an output with the cues `split_line` proposes.
+///
+/// Lines that fit and blank screens (`---`) are kept as written.
+pub fn split_long_lines(lines: Vec<String>, rule: &OutputRule) -> Vec<String> {
+    let max_chars = rule.max_chars as usize;
+    let max_rows = rule.max_rows as usize;
+
+    lines
+        .into_iter()
+        .flat_map(|line| {
+            let fits = line == "---"
+                || parse_markup(&line).is_ok_and(|spans| {
+                    let (rows, longest_row) = measure(&spans);
+                    rows <= max_rows && longest_row <= max_chars
+                });
+
+            match fits {
+                true => vec![line],
+                false => split_line(&line, max_chars, max_rows).unwrap_or_else(|_| vec![line]),
+            }
+        })
+        .collect()
+}
+
+/// A line too long for an output, with a way to make it fit.
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct LineFit {
+    pub song_id: i32,
+    pub line_id: i32,
+    pub line: String,
+    pub rows: usize,
+    pub longest_row: usize,
+    /// The line split into cues that fit, see `split_line`.
+    pub proposal: Vec<String>,
+}
+
+fn load_rule(con: &mut PgConnection, id: i32) -> QueryResult<OutputRule> {
+    output_rule::table
+        .find(id)
+        .select(OutputRule::as_select())
+        .first(con)
+}
+
+/// Loads an output's rule by name, for song imports.
+pub fn load_rule_named(con: &mut PgConnection, name: &str) -> QueryResult<Option<OutputRule>> {
+    output_rule::table
+        .filter(output_rule::name.eq(name))
+        .select(OutputRule::as_select())
+        .first(con)
+        .optional()
+}
+
+/// Lines of a song breaking the rule, every line of a block once.
+fn song_fits(con: &mut PgConnection, song_id: i32, rule: &OutputRule) -> QueryResult<Vec<LineFit>> {
+    let max_chars = rule.max_chars as usize;
+    let max_rows = rule.max_rows as usize;
+    let mut seen = HashSet::new();
+
+    let fits = load_song_lines(con, song_id)?
+        .into_iter()
+        .filter(|line| seen.insert(line.id) && line.kind != LineKind::StageDirection)
+        .filter_map(|line| {
+            let (rows, longest_row) = measure(&line.spans);
+            if rows <= max_rows && longest_row <= max_chars {
+                return None;
+            }
+
+            Some(LineFit {
+                song_id,
+                line_id: line.id,
+                proposal: split_line(&line.line, max_chars, max_rows).unwrap_or_default(),
+                line: line.line,
+                rows,
+                longest_row,
+            })
+        })
+        .collect();
+
+    Ok(fits)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FitRequest {
+    output: i32,
+    /// Every song when not set.
+    song_id: Option<i32>,
+}
+
+/// Lists the lines too long for an output.
+pub async fn get_fit_report(
+    State(state): State<Store>,
+    Query(req): Query<FitRequest>,
+) -> Result<Json<Vec<LineFit>>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            let rule = load_rule(con, req.output)?;
+
+            let song_ids = match req.song_id {
+                Some(song_id) => vec![song_id],
+                None => song::table
+                    .select(song::id)
+                    .order(song::id.asc())
+                    .load(con)?,
+            };
+
+            let mut fits = Vec::new();
+            for song_id in song_ids {
+                fits.extend(song_fits(con, song_id, &rule)?);
+            }
+
+            QueryResult::Ok(fits)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(fits) => Ok(Json(fits)),
+        Err(diesel::result::Error::NotFound) => Err((StatusCode::NOT_FOUND, "Output not found")),
+        Err(e) => {
+            error!("Failed to check lines for output {}: {}", req.output, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to check lines"))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SplitRequest {
+    line: String,
+    output: i32,
+}
+
+/// Proposes how to split a line for an output.
+pub async fn split_for_output(
+    State(state): State<Store>,
+    Json(body): Json<SplitRequest>,
+) -> Result<Json<Vec<String>>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let rule = pool
+        .interact(move |con| load_rule(con, body.output))
+        .await
+        .unwrap()
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => (StatusCode::NOT_FOUND, "Output not found"),
+            e => {
+                error!("Failed to load output rule: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load output")
+            }
+        })?;
+
+    split_line(&body.line, rule.max_chars as usize, rule.max_rows as usize)
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+pub async fn get_output_rules(
+    State(state): State<Store>,
+) -> Result<Json<Vec<OutputRule>>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(|con| {
+            output_rule::table
+                .select(OutputRule::as_select())
+                .order(output_rule::name.asc())
+                .load(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(rules) => Ok(Json(rules)),
+        Err(e) => {
+            error!("Failed to load output rules: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to load outputs"))
+        }
+    }
+}
+
+/// Checks an output rule sent by a client.
+fn validate(body: &OutputRule) -> Result<String, (StatusCode, &'static str)> {
+    let name = body.name.trim().to_string();
+
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Output name is empty"));
+    }
+    if body.max_chars < 1 || body.max_rows < 1 {
+        return Err((StatusCode::BAD_REQUEST, "Outputs need room for a character"));
+    }
+
+    Ok(name)
+}
+
+pub async fn add_output_rule(
+    State(state): State<Store>,
+    Json(body): Json<OutputRule>,
+) -> Result<Json<OutputRule>, (StatusCode, &'static str)> {
+    let name = validate(&body)?;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            diesel::insert_into(output_rule::table)
+                .values((
+                    output_rule::name.eq(name),
+                    output_rule::max_chars.eq(body.max_chars),
+                    output_rule::max_rows.eq(body.max_rows),
+                ))
+                .returning(OutputRule::as_returning())
+                .get_result(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(added) => {
+            info!("Added output rule with id: {}", added.id);
+            Ok(Json(added))
+        }
+        Err(e) => {
+            error!("Failed to add output rule: {}", e);
+            Err((StatusCode::CONFLICT, "Failed to add output"))
+        }
+    }
+}
+
+pub async fn edit_output_rule(
+    State(state): State<Store>,
+    Json(body): Json<OutputRule>,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    let name = validate(&body)?;
+    let id = body.id;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            diesel::update(output_rule::table.find(id))
+                .set((
+                    output_rule::name.eq(name),
+                    output_rule::max_chars.eq(body.max_chars),
+                    output_rule::max_rows.eq(body.max_rows),
+                ))
+                .execute(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(0) => Err((StatusCode::NOT_FOUND, "Output not found")),
+        Ok(_) => {
+            info!("Updated output rule with id: {}", id);
+            Ok(StatusCode::OK)
+        }
+        Err(e) => {
+            error!("Failed to update output rule {}: {}", id, e);
+            Err((StatusCode::CONFLICT, "Failed to update output"))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OutputRuleRequest {
+    id: i32,
+}
+
+pub async fn delete_output_rule(
+    State(state): State<Store>,
+    Json(body): Json<OutputRuleRequest>,
+) -> StatusCode {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| diesel::delete(output_rule::table.find(body.id)).execute(con))
+        .await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        error!("Failed to delete output rule with id: {}", body.id);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    info!("Deleted output rule with id: {}", body.id);
+
+    StatusCode::OK
+}
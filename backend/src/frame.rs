@@ -0,0 +1,69 @@
+use crate::{
+    camera::CameraPose,
+    types::{Frame, FrameLine, LineComp, LineKind, LineStyle},
+    ActiveSong,
+};
+
+/// The 1-based index of the line the displays show with the cursor at `line`.
+///
+/// Stage directions are never broadcast, so the last line before them stays up.
+pub fn shown_index(line: u32, song_lines: &[LineComp]) -> Option<u32> {
+    let end = (line as usize).min(song_lines.len());
+
+    song_lines[..end]
+        .iter()
+        .rposition(|line| line.kind != LineKind::StageDirection)
+        .map(|position| position as u32 + 1)
+}
+
+/// The lines of the active song on the displays, as the scene shows them.
+///
+/// The current line keeps its `keep_n_last` previous lines in play order up,
+/// each at its own position. Lines without anything to show are left out.
+pub fn song_frame(active_song: &ActiveSong, song_lines: &[LineComp], style: &LineStyle) -> Frame {
+    let index = shown_index(active_song.line, song_lines);
+
+    let Some(current_index) = index.map(|index| index as usize - 1) else {
+        return Frame {
+            song_id: active_song.id,
+            index: None,
+            camera: None,
+            lines: Vec::new(),
+            started_at: None,
+        };
+    };
+
+    let current = &song_lines[current_index];
+    let first = current_index.saturating_sub(current.keep_n_last.max(0) as usize);
+
+    let lines = song_lines[first..=current_index]
+        .iter()
+        .enumerate()
+        .filter_map(|(offset, line)| {
+            let text = line.audience_text().filter(|text| !text.is_empty())?;
+
+            Some(FrameLine {
+                line_id: line.id,
+                line_index: first + offset,
+                current: first + offset == current_index,
+                text,
+                spans: line.spans.clone(),
+                style: line.style.clone().or(style),
+                position: line.position.clone(),
+                end_position: line.end_position.clone(),
+                rotation: line.rotation.clone(),
+            })
+        })
+        .collect();
+
+    Frame {
+        song_id: active_song.id,
+        index,
+        camera: Some(CameraPose {
+            position: current.cam_position.clone(),
+            look_at: current.cam_look_at.clone(),
+        }),
+        lines,
+        started_at: active_song.cued_at,
+    }
+}
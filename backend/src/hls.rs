@@ -0,0 +1,224 @@
+use std::fmt::Write;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::info;
+
+use crate::Store;
+
+/// Length of every subtitle segment.
+const SEGMENT_SECS: i64 = 6;
+
+/// Segments listed in the live playlist, older ones are dropped.
+const WINDOW_SEGMENTS: i64 = 10;
+
+/// A line as it was shown on the displays.
+#[derive(Debug, Clone)]
+struct LiveCue {
+    text: String,
+    start: DateTime<Utc>,
+    /// `None` while it is still showing.
+    end: Option<DateTime<Utc>>,
+}
+
+/// What the displays showed lately, cut into WebVTT segments for HLS.
+///
+/// Segments are numbered from when the server started, segment `n` covering
+/// `SEGMENT_SECS` from `epoch + n * SEGMENT_SECS`, and cue times count from
+/// `epoch` as well.
+///
+/// Players don't place WebVTT cues by `EXT-X-PROGRAM-DATE-TIME` but by the
+/// `X-TIMESTAMP-MAP` of every segment, which maps `epoch` to the MPEG-TS
+/// timestamp `mpegts`. For the captions to line up, it has to be the PTS the
+/// video had when the server started, taken from `LIVE_CAPTIONS_MPEGTS`
+/// (90 kHz ticks, 0 when not set).
+#[derive(Debug)]
+pub struct LiveCaptions {
+    epoch: DateTime<Utc>,
+    mpegts: u64,
+    cues: Vec<LiveCue>,
+}
+
+impl LiveCaptions {
+    pub fn new(epoch: DateTime<Utc>) -> Self {
+        let mpegts = std::env::var("LIVE_CAPTIONS_MPEGTS")
+            .map(|mpegts| {
+                mpegts
+                    .parse()
+                    .expect("LIVE_CAPTIONS_MPEGTS must be a number of 90 kHz ticks")
+            })
+            .unwrap_or(0);
+
+        LiveCaptions {
+            epoch,
+            mpegts,
+            cues: Vec::new(),
+        }
+    }
+
+    fn segment_start(&self, sequence: i64) -> DateTime<Utc> {
+        self.epoch + Duration::seconds(sequence * SEGMENT_SECS)
+    }
+
+    /// The segment being written at `now`, only earlier ones are complete.
+    fn current_segment(&self, now: DateTime<Utc>) -> i64 {
+        (now - self.epoch).num_seconds() / SEGMENT_SECS
+    }
+
+    /// Complete segments still kept, oldest first.
+    fn window(&self, now: DateTime<Utc>) -> std::ops::Range<i64> {
+        let current = self.current_segment(now);
+        (current - WINDOW_SEGMENTS).max(0)..current
+    }
+
+    /// Ends the line showing at `at` and starts showing `text`, empty for nothing.
+    fn show(&mut self, text: &str, at: DateTime<Utc>) {
+        if let Some(cue) = self.cues.last_mut().filter(|cue| cue.end.is_none()) {
+            cue.end = Some(at);
+        }
+
+        if !text.trim().is_empty() {
+            self.cues.push(LiveCue {
+                text: text.to_string(),
+                start: at,
+                end: None,
+            });
+        }
+
+        // Nothing can ask for cues ending before the oldest segment kept
+        let oldest = self.segment_start(self.window(at).start);
+        self.cues
+            .retain(|cue| cue.end.is_none_or(|end| end > oldest));
+    }
+
+    pub fn playlist(&self, now: DateTime<Utc>) -> String {
+        let window = self.window(now);
+
+        let mut playlist = format!(
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:{SEGMENT_SECS}\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+            window.start
+        );
+        for sequence in window {
+            let _ = write!(
+                playlist,
+                "#EXT-X-PROGRAM-DATE-TIME:{}\n#EXTINF:{SEGMENT_SECS}.000,\n{sequence}.vtt\n",
+                self.segment_start(sequence)
+                    .to_rfc3339_opts(SecondsFormat::Millis, true)
+            );
+        }
+
+        playlist
+    }
+
+    /// The cues showing during a segment, `None` if it isn't complete or was dropped.
+    pub fn segment(&self, sequence: i64, now: DateTime<Utc>) -> Option<String> {
+        if !self.window(now).contains(&sequence) {
+            return None;
+        }
+
+        let start = self.segment_start(sequence);
+        let end = self.segment_start(sequence + 1);
+
+        let mut vtt = format!(
+            "WEBVTT\nX-TIMESTAMP-MAP=MPEGTS:{},LOCAL:00:00:00.000\n",
+            self.mpegts
+        );
+        for cue in &self.cues {
+            // Lines still showing end with the segment and carry on in the next one
+            let cue_end = cue.end.unwrap_or(end).min(end);
+            if cue.start >= end || cue_end <= start {
+                continue;
+            }
+
+            let _ = write!(
+                vtt,
+                "\n{} --> {}\n{}\n",
+                self.timestamp(cue.start),
+                self.timestamp(cue_end),
+                cue_text(&cue.text)
+            );
+        }
+
+        Some(vtt)
+    }
+
+    /// Time since `epoch` as a WebVTT timestamp.
+    fn timestamp(&self, at: DateTime<Utc>) -> String {
+        let ms = (at - self.epoch).num_milliseconds().max(0);
+        format!(
+            "{:02}:{:02}:{:02}.{:03}",
+            ms / 3_600_000,
+            ms / 60_000 % 60,
+            ms / 1000 % 60,
+            ms % 1000
+        )
+    }
+}
+
+/// Escapes a line for a WebVTT cue, which ends at the first empty row.
+pub fn cue_text(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|row| !row.is_empty())
+        .map(|row| {
+            row.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Records everything the displays show for the live captions, for as long as the server runs.
+pub fn record_live_captions(state: Store) {
+    let mut receiver = state.line_ch.subscribe();
+
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(text) => state.live_captions.write().await.show(&text, Utc::now()),
+                Err(RecvError::Lagged(skipped)) => {
+                    info!("Live captions skipped {} lines", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+pub async fn get_live_playlist(State(state): State<Store>) -> Response {
+    let playlist = state.live_captions.read().await.playlist(Utc::now());
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/vnd.apple.mpegurl"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        playlist,
+    )
+        .into_response()
+}
+
+pub async fn get_live_segment(
+    State(state): State<Store>,
+    Path(file): Path<String>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    let sequence = file
+        .strip_suffix(".vtt")
+        .and_then(|sequence| sequence.parse::<i64>().ok())
+        .ok_or((StatusCode::NOT_FOUND, "Segment not found"))?;
+
+    let segment = state
+        .live_captions
+        .read()
+        .await
+        .segment(sequence, Utc::now())
+        .ok_or((StatusCode::NOT_FOUND, "Segment not found"))?;
+
+    Ok(([(header::CONTENT_TYPE, "text/vtt")], segment).into_response())
+}
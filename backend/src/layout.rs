@@ -0,0 +1,353 @@
+use std::collections::HashSet;
+
+use axum::{extract::State, http::StatusCode, Json};
+use diesel::{Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use pgvector::Vector;
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::{
+    schema::lines,
+    structure::load_song_lines,
+    types::{NewDbLineComp, Vector3},
+    Store,
+};
+
+/// Attempts at finding a free spot before a random layout settles for the least crowded one.
+const RANDOM_ATTEMPTS: usize = 64;
+
+/// How a layout arranges the lines of a song.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Layout {
+    /// Every line at the same spot, how songs were imported before layouts.
+    Stack,
+    /// Each line `step` away from the previous one.
+    Walk {
+        #[serde(default = "default_walk_step")]
+        step: Vector3,
+    },
+    /// Lines around the y axis, moving `height_step` up or down every line.
+    Spiral {
+        #[serde(default = "default_spiral_radius")]
+        radius: f32,
+        #[serde(default)]
+        radius_step: f32,
+        #[serde(default = "default_spiral_angle")]
+        angle_step_deg: f32,
+        #[serde(default = "default_spiral_height")]
+        height_step: f32,
+    },
+    /// Rows of `columns` lines, read left to right and top to bottom.
+    Grid {
+        #[serde(default = "default_grid_columns")]
+        columns: u32,
+        #[serde(default = "default_grid_spacing")]
+        spacing: Vector3,
+    },
+    /// Anywhere between `min` and `max`, keeping lines `min_distance` apart where possible.
+    Random {
+        #[serde(default = "default_random_min")]
+        min: Vector3,
+        #[serde(default = "default_random_max")]
+        max: Vector3,
+        #[serde(default = "default_random_distance")]
+        min_distance: f32,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+fn default_walk_step() -> Vector3 {
+    Vector3 {
+        x: 60.0,
+        y: 0.0,
+        z: -30.0,
+    }
+}
+
+fn default_spiral_radius() -> f32 {
+    100.0
+}
+
+fn default_spiral_angle() -> f32 {
+    30.0
+}
+
+fn default_spiral_height() -> f32 {
+    -10.0
+}
+
+fn default_grid_columns() -> u32 {
+    4
+}
+
+fn default_grid_spacing() -> Vector3 {
+    Vector3 {
+        x: 120.0,
+        y: 40.0,
+        z: 0.0,
+    }
+}
+
+fn default_random_min() -> Vector3 {
+    Vector3 {
+        x: -300.0,
+        y: -100.0,
+        z: -300.0,
+    }
+}
+
+fn default_random_max() -> Vector3 {
+    Vector3 {
+        x: 300.0,
+        y: 100.0,
+        z: 0.0,
+    }
+}
+
+fn default_random_distance() -> f32 {
+    60.0
+}
+
+/// Where the camera sits relative to the text it looks at.
+pub const DEFAULT_CAM_OFFSET: Vector3 = Vector3 {
+    x: 0.0,
+    y: 10.0,
+    z: 150.0,
+};
+
+fn default_cam_offset() -> Vector3 {
+    DEFAULT_CAM_OFFSET
+}
+
+impl Layout {
+    /// The layout a song import asks for by name, with default parameters.
+    pub fn named(name: &str, seed: Option<u64>) -> Option<Layout> {
+        match name {
+            "stack" => Some(Layout::Stack),
+            "walk" => Some(Layout::Walk {
+                step: default_walk_step(),
+            }),
+            "spiral" => Some(Layout::Spiral {
+                radius: default_spiral_radius(),
+                radius_step: 0.0,
+                angle_step_deg: default_spiral_angle(),
+                height_step: default_spiral_height(),
+            }),
+            "grid" => Some(Layout::Grid {
+                columns: default_grid_columns(),
+                spacing: default_grid_spacing(),
+            }),
+            "random" => Some(Layout::Random {
+                min: default_random_min(),
+                max: default_random_max(),
+                min_distance: default_random_distance(),
+                seed: seed.unwrap_or_default(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// SplitMix64, small and good enough to scatter text, and the same on every machine for a seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[min, max)`.
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        min + (max - min) * unit
+    }
+}
+
+fn distance(a: &Vector3, b: &Vector3) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+}
+
+fn random_positions(
+    count: usize,
+    min: &Vector3,
+    max: &Vector3,
+    min_distance: f32,
+    seed: u64,
+) -> Vec<Vector3> {
+    let mut rng = SplitMix64(seed);
+    let mut placed: Vec<Vector3> = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let mut best: Option<(f32, Vector3)> = None;
+
+        for _ in 0..RANDOM_ATTEMPTS {
+            let candidate = Vector3 {
+                x: rng.range(min.x, max.x),
+                y: rng.range(min.y, max.y),
+                z: rng.range(min.z, max.z),
+            };
+            let clearance = placed
+                .iter()
+                .map(|other| distance(other, &candidate))
+                .fold(f32::INFINITY, f32::min);
+
+            if best.as_ref().is_none_or(|(best, _)| clearance > *best) {
+                best = Some((clearance, candidate));
+            }
+            if clearance >= min_distance {
+                break;
+            }
+        }
+
+        placed.push(best.map(|(_, position)| position).unwrap_or_default());
+    }
+
+    placed
+}
+
+/// Text positions for `count` lines.
+pub fn positions(layout: &Layout, count: usize) -> Vec<Vector3> {
+    match layout {
+        Layout::Stack => vec![Vector3::default(); count],
+        Layout::Walk { step } => (0..count)
+            .map(|i| {
+                let i = i as f32;
+                Vector3 {
+                    x: step.x * i,
+                    y: step.y * i,
+                    z: step.z * i,
+                }
+            })
+            .collect(),
+        Layout::Spiral {
+            radius,
+            radius_step,
+            angle_step_deg,
+            height_step,
+        } => (0..count)
+            .map(|i| {
+                let i = i as f32;
+                let angle = (angle_step_deg * i).to_radians();
+                let radius = radius + radius_step * i;
+                Vector3 {
+                    x: radius * angle.sin(),
+                    y: height_step * i,
+                    z: radius * angle.cos(),
+                }
+            })
+            .collect(),
+        Layout::Grid { columns, spacing } => {
+            let columns = (*columns).max(1) as usize;
+            (0..count)
+                .map(|i| Vector3 {
+                    x: spacing.x * (i % columns) as f32,
+                    y: -spacing.y * (i / columns) as f32,
+                    z: spacing.z * (i / columns) as f32,
+                })
+                .collect()
+        }
+        Layout::Random {
+            min,
+            max,
+            min_distance,
+            seed,
+        } => random_positions(count, min, max, *min_distance, *seed),
+    }
+}
+
+/// Where a line's text goes, and the camera looking at it.
+pub struct Placement {
+    pub position: Vector3,
+    pub cam_position: Vector3,
+    pub cam_look_at: Vector3,
+}
+
+/// Places `count` lines, with the camera `cam_offset` away from each line.
+pub fn placements(layout: &Layout, count: usize, cam_offset: &Vector3) -> Vec<Placement> {
+    positions(layout, count)
+        .into_iter()
+        .map(|position| Placement {
+            cam_position: Vector3 {
+                x: position.x + cam_offset.x,
+                y: position.y + cam_offset.y,
+                z: position.z + cam_offset.z,
+            },
+            cam_look_at: position.clone(),
+            position,
+        })
+        .collect()
+}
+
+impl Placement {
+    pub fn apply(self, row: &mut NewDbLineComp) {
+        row.position = self.position.into();
+        row.cam_position = self.cam_position.into();
+        row.cam_look_at = self.cam_look_at.into();
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LayoutRequest {
+    song_id: i32,
+    layout: Layout,
+    #[serde(default = "default_cam_offset")]
+    cam_offset: Vector3,
+}
+
+/// Lays out the lines of an existing song in play order, returning how many lines moved.
+///
+/// Lines of blocks played several times are shared, so they are placed where
+/// the block is first played.
+pub async fn apply_layout(
+    State(state): State<Store>,
+    Json(body): Json<LayoutRequest>,
+) -> Result<Json<usize>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let song_id = body.song_id;
+
+    let res = pool
+        .interact(move |con| {
+            con.transaction(|tran| {
+                let mut seen = HashSet::new();
+                let line_ids = load_song_lines(tran, body.song_id)?
+                    .into_iter()
+                    .map(|line| line.id)
+                    .filter(|id| seen.insert(*id))
+                    .collect::<Vec<_>>();
+
+                let placed = placements(&body.layout, line_ids.len(), &body.cam_offset);
+
+                for (line_id, placement) in line_ids.iter().zip(placed) {
+                    diesel::update(lines::table.find(line_id))
+                        .set((
+                            lines::position.eq(Vector::from(placement.position)),
+                            lines::cam_position.eq(Vector::from(placement.cam_position)),
+                            lines::cam_look_at.eq(Vector::from(placement.cam_look_at)),
+                        ))
+                        .execute(tran)?;
+                }
+
+                QueryResult::Ok(line_ids.len())
+            })
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(moved) => {
+            info!("Laid out {} lines of song {}", moved, song_id);
+            Ok(Json(moved))
+        }
+        Err(e) => {
+            error!("Failed to lay out song {}: {}", song_id, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to lay out song"))
+        }
+    }
+}
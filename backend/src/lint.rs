@@ -0,0 +1,258 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use ts_rs::TS;
+
+use crate::{
+    markup::parse_markup,
+    schema::song,
+    structure::load_song_lines,
+    types::{LineComp, LineKind, Vector3},
+    Store,
+};
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum LintLevel {
+    Warning,
+    /// Shows up wrong on the displays for sure.
+    Error,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum LintCode {
+    LookAtIsPosition,
+    TextBehindCamera,
+    UpsideDownText,
+    KeepNLastTooLarge,
+    InvalidMarkup,
+    EmptyLyric,
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct LintWarning {
+    pub line_id: i32,
+    /// Position of the line in `LoadSong::lines`.
+    pub index: usize,
+    pub level: LintLevel,
+    pub code: LintCode,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct SongLint {
+    pub song_id: i32,
+    pub title: String,
+    pub warnings: Vec<LintWarning>,
+}
+
+/// Anything closer than this counts as the same point.
+const EPSILON: f32 = 1e-3;
+
+fn sub(a: &Vector3, b: &Vector3) -> Vector3 {
+    Vector3 {
+        x: a.x - b.x,
+        y: a.y - b.y,
+        z: a.z - b.z,
+    }
+}
+
+fn dot(a: &Vector3, b: &Vector3) -> f32 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+/// Where the up vector of the text points after `rotation`, Euler angles in
+/// radians applied in XYZ order like the scene does.
+fn rotated_up(rotation: &Vector3) -> Vector3 {
+    let (sx, cx) = rotation.x.sin_cos();
+    let (sy, cy) = rotation.y.sin_cos();
+    let (sz, cz) = rotation.z.sin_cos();
+
+    // Second column of Rx * Ry * Rz
+    Vector3 {
+        x: -cy * sz,
+        y: cx * cz - sx * sy * sz,
+        z: sx * cz + cx * sy * sz,
+    }
+}
+
+/// Checks the camera looking at `look_at` from `position` can see `text`.
+fn lint_camera(
+    position: &Vector3,
+    look_at: &Vector3,
+    text: &Vector3,
+    which: &str,
+    warn: &mut impl FnMut(LintLevel, LintCode, String),
+) {
+    let forward = sub(look_at, position);
+
+    if dot(&forward, &forward) < EPSILON * EPSILON {
+        warn(
+            LintLevel::Error,
+            LintCode::LookAtIsPosition,
+            format!("The {which} camera looks at its own position"),
+        );
+        return;
+    }
+
+    if dot(&forward, &sub(text, position)) <= 0.0 {
+        warn(
+            LintLevel::Error,
+            LintCode::TextBehindCamera,
+            format!("The text is behind the {which} camera"),
+        );
+    }
+}
+
+/// Checks every line of a song, in play order.
+pub fn lint_lines(song_lines: &[LineComp]) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+
+    for (index, line) in song_lines.iter().enumerate() {
+        let mut warn = |level, code, message| {
+            warnings.push(LintWarning {
+                line_id: line.id,
+                index,
+                level,
+                code,
+                message,
+            })
+        };
+
+        // Stage directions never reach the displays
+        if line.kind == LineKind::StageDirection {
+            continue;
+        }
+
+        if let Err(e) = parse_markup(&line.line) {
+            warn(LintLevel::Warning, LintCode::InvalidMarkup, e.to_string());
+        }
+        if line.kind == LineKind::Lyric && line.line.trim().is_empty() {
+            warn(
+                LintLevel::Warning,
+                LintCode::EmptyLyric,
+                "Empty lyric, use a blank line instead".to_string(),
+            );
+        }
+
+        if line.keep_n_last as usize > index {
+            warn(
+                LintLevel::Warning,
+                LintCode::KeepNLastTooLarge,
+                format!(
+                    "Keeps {} previous lines, but only {} come before it",
+                    line.keep_n_last, index
+                ),
+            );
+        }
+
+        if let Some(rotation) = &line.rotation {
+            if rotated_up(rotation).y < 0.0 {
+                warn(
+                    LintLevel::Warning,
+                    LintCode::UpsideDownText,
+                    "The rotation turns the text upside down".to_string(),
+                );
+            }
+        }
+
+        lint_camera(
+            &line.cam_position,
+            &line.cam_look_at,
+            &line.position,
+            "start",
+            &mut warn,
+        );
+
+        if line.cam_end_position.is_some() || line.cam_end_look_at.is_some() {
+            lint_camera(
+                line.cam_end_position.as_ref().unwrap_or(&line.cam_position),
+                line.cam_end_look_at.as_ref().unwrap_or(&line.cam_look_at),
+                line.end_position.as_ref().unwrap_or(&line.position),
+                "end",
+                &mut warn,
+            );
+        }
+    }
+
+    warnings
+}
+
+fn lint_song(con: &mut PgConnection, song_id: i32) -> QueryResult<SongLint> {
+    let title = song::table
+        .find(song_id)
+        .select(song::name)
+        .first::<String>(con)?;
+
+    let song_lines = load_song_lines(con, song_id)?;
+
+    Ok(SongLint {
+        song_id,
+        title,
+        warnings: lint_lines(&song_lines),
+    })
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LintRequest {
+    id: i32,
+}
+
+pub async fn get_song_lint(
+    State(state): State<Store>,
+    Query(req): Query<LintRequest>,
+) -> Result<Json<SongLint>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| lint_song(con, req.id))
+        .await
+        .unwrap();
+
+    match res {
+        Ok(lint) => Ok(Json(lint)),
+        Err(diesel::result::Error::NotFound) => Err((StatusCode::NOT_FOUND, "Song not found")),
+        Err(e) => {
+            error!("Failed to lint song {}: {}", req.id, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to lint song"))
+        }
+    }
+}
+
+/// Lints every song, for a check of the whole setlist before the show.
+pub async fn get_lint(
+    State(state): State<Store>,
+) -> Result<Json<Vec<SongLint>>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(|con| {
+            song::table
+                .select(song::id)
+                .order(song::id.asc())
+                .load::<i32>(con)?
+                .into_iter()
+                .map(|song_id| lint_song(con, song_id))
+                .collect::<QueryResult<Vec<_>>>()
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(lints) => Ok(Json(lints)),
+        Err(e) => {
+            error!("Failed to lint songs: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to lint songs"))
+        }
+    }
+}
@@ -3,46 +3,108 @@ use std::{
     sync::Arc,
 };
 
+use announcement::{delete_announcement, set_announcement};
 use axum::{
     extract::MatchedPath,
     http::{Method, Request},
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Router,
 };
+use camera::get_camera_path;
+use captions::get_captions;
+use character::{add_character, assign_speaker, delete_character, edit_character, get_characters};
+use chrono::{DateTime, Utc};
 use controller::{
-    add_song, delete_line, edit_song, get_all_songs, get_line, get_song, next_line, reset_line,
-    set_active_song,
+    add_section, add_song, delete_line, delete_section, edit_song, get_all_songs, get_line,
+    get_song, jump_to_section, next_line, reset_line, set_active_song,
 };
+use countdown::{
+    cancel_countdown, extend_countdown, pause_countdown, resume_countdown, start_countdown,
+};
+use cue_log::{get_cue_log, get_cue_report};
+use cue_state::load_cue_state;
 use deadpool_diesel::{Manager, Pool};
 use diesel::prelude::*;
-use sse::{sse_handler_active_line, sse_handler_lines, sse_load_song, sse_scene_ready};
+use fit::{
+    add_output_rule, delete_output_rule, edit_output_rule, get_fit_report, get_output_rules,
+    split_for_output,
+};
+use hls::{get_live_playlist, get_live_segment, record_live_captions, LiveCaptions};
+use layout::apply_layout;
+use lint::{get_lint, get_song_lint};
+use output::Output;
+use patch::{bulk_edit_lines, patch_line};
+use preset::{add_preset, delete_preset, edit_preset, get_presets};
+use render::{get_render_page, get_render_png, get_render_stream, get_render_svg};
+use scc::get_song_scc;
+use sse::{
+    sse_announcement, sse_countdown, sse_cue, sse_frame, sse_handler_active_line,
+    sse_handler_lines, sse_load_song, sse_operator, sse_scene_ready,
+};
+use structure::{add_block, delete_block, get_structure, set_structure};
+use style::set_song_style;
+use timing::{import_lrc, set_line_timings};
 use tokio::sync::{broadcast, RwLock};
 use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
 use tracing::info_span;
-use types::LoadSong;
+use types::{Announcement, Countdown, Cue, Frame, LoadSong, OperatorCue};
+use vamp::{add_vamp, break_vamp, delete_vamp};
 
+mod announcement;
+mod camera;
+mod captions;
+mod character;
 mod controller;
+mod countdown;
+mod cue_log;
+mod cue_state;
+mod fit;
+mod frame;
+mod hls;
+mod layout;
+mod lint;
+mod markup;
+mod output;
+mod patch;
+mod preset;
+mod render;
+mod scc;
 pub mod schema;
 mod sse;
+mod structure;
+mod style;
+mod timing;
 mod types;
+mod vamp;
 
 #[derive(Debug, Clone, Copy, Default)]
 struct ActiveSong {
     id: i32,
     line: u32,
+    /// The loop the operator has broken out of, while the cursor is still inside it.
+    broken_vamp: Option<i32>,
+    /// When the line under the cursor was cued.
+    cued_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone)]
 struct Store {
     line_ch: Arc<broadcast::Sender<String>>,
     index_ch: Arc<broadcast::Sender<Option<u32>>>,
+    cue_ch: Arc<broadcast::Sender<Cue>>,
+    frame_ch: Arc<broadcast::Sender<Frame>>,
     load_song_ch: Arc<broadcast::Sender<LoadSong>>,
+    operator_ch: Arc<broadcast::Sender<OperatorCue>>,
+    announcement_ch: Arc<broadcast::Sender<Option<Announcement>>>,
+    countdown_ch: Arc<broadcast::Sender<Option<Countdown>>>,
     scene_ready: Arc<broadcast::Sender<bool>>,
     pool: Arc<Pool<Manager<PgConnection>>>,
     active_song: Arc<RwLock<ActiveSong>>,
+    output: Arc<RwLock<Output>>,
+    live_captions: Arc<RwLock<LiveCaptions>>,
 }
 
 impl Display for Store {
@@ -62,6 +124,11 @@ async fn main() {
     let (tx, _) = broadcast::channel::<String>(16);
     let (index_tx, _) = broadcast::channel::<Option<u32>>(16);
     let (song_tx, _) = broadcast::channel::<LoadSong>(16);
+    let (cue_tx, _) = broadcast::channel::<Cue>(16);
+    let (frame_tx, _) = broadcast::channel::<Frame>(16);
+    let (operator_tx, _) = broadcast::channel::<OperatorCue>(16);
+    let (announcement_tx, _) = broadcast::channel::<Option<Announcement>>(16);
+    let (countdown_tx, _) = broadcast::channel::<Option<Countdown>>(16);
     let (scene_tx, _) = broadcast::channel::<bool>(16);
 
     let db_url = std::env::var("DATABASE_URL").unwrap();
@@ -73,42 +140,113 @@ async fn main() {
         .build()
         .unwrap();
 
-    let active_song = ActiveSong::default();
+    let active_song = load_cue_state(&pool).await;
 
     let state = Store {
         line_ch: Arc::new(tx),
         index_ch: Arc::new(index_tx),
+        cue_ch: Arc::new(cue_tx),
+        frame_ch: Arc::new(frame_tx),
         load_song_ch: Arc::new(song_tx),
+        operator_ch: Arc::new(operator_tx),
+        announcement_ch: Arc::new(announcement_tx),
+        countdown_ch: Arc::new(countdown_tx),
         scene_ready: Arc::new(scene_tx),
         pool: Arc::new(pool),
         active_song: Arc::new(RwLock::new(active_song)),
+        output: Arc::new(RwLock::new(Output::default())),
+        live_captions: Arc::new(RwLock::new(LiveCaptions::new(Utc::now()))),
     };
 
+    record_live_captions(state.clone());
+
     let cors_layer = CorsLayer::new()
         // allow `GET` and `POST` when accessing the resource
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
         .allow_headers(Any)
         // allow requests from any origin
         .allow_origin(Any);
 
     let sse_router = Router::new()
+        .route("/announcement", get(sse_announcement))
+        .route("/countdown", get(sse_countdown))
+        .route("/cue", get(sse_cue))
+        .route("/frame", get(sse_frame))
         .route("/sse", get(sse_handler_lines))
         .route("/line", get(sse_handler_active_line))
         .route("/load", get(sse_load_song))
+        .route("/operator", get(sse_operator))
         .route("/ready", get(sse_scene_ready))
         .layer(cors_layer.clone())
         .with_state(state.clone());
 
     // build our application with a route
     let app = Router::new()
+        .route("/announcement", post(set_announcement))
+        .route("/announcement", delete(delete_announcement))
+        .route("/captions", get(get_captions))
+        .route("/character", post(add_character))
+        .route("/character", put(edit_character))
+        .route("/character", delete(delete_character))
+        .route("/characters", get(get_characters))
+        .route("/countdown", post(start_countdown))
+        .route("/countdown", delete(cancel_countdown))
+        .route("/countdown/extend", post(extend_countdown))
+        .route("/countdown/pause", post(pause_countdown))
+        .route("/countdown/resume", post(resume_countdown))
+        .route("/cuelog", get(get_cue_log))
         .route("/edit/line", get(get_line))
+        .route("/fit/report", get(get_fit_report))
+        .route("/fit/split", post(split_for_output))
+        .route("/lint", get(get_lint))
+        .route("/live/captions.m3u8", get(get_live_playlist))
+        .route("/live/{file}", get(get_live_segment))
+        .route("/output", post(add_output_rule))
+        .route("/output", put(edit_output_rule))
+        .route("/output", delete(delete_output_rule))
+        .route("/outputs", get(get_output_rules))
+        .route("/preset", post(add_preset))
+        .route("/preset", put(edit_preset))
+        .route("/preset", delete(delete_preset))
+        .route("/presets", get(get_presets))
+        .route("/report", get(get_cue_report))
+        .route("/render/page", get(get_render_page))
+        .route("/render/png", get(get_render_png))
+        .route("/render/stream", get(get_render_stream))
+        .route("/render/svg", get(get_render_svg))
         .route("/reset", post(reset_line))
         .route("/song", get(get_song))
         .route("/song", post(add_song))
         .route("/song/edit", delete(delete_line))
         .route("/song/edit", put(edit_song))
+        .route("/song/edit", patch(patch_line))
+        .route("/song/edit/bulk", post(bulk_edit_lines))
+        .route("/song/block", post(add_block))
+        .route("/song/block", delete(delete_block))
+        .route("/song/jump", post(jump_to_section))
+        .route("/song/layout", post(apply_layout))
+        .route("/song/lint", get(get_song_lint))
         .route("/song/next", post(next_line))
+        .route("/song/path", get(get_camera_path))
+        .route("/song/scc", get(get_song_scc))
+        .route("/song/section", post(add_section))
+        .route("/song/section", delete(delete_section))
         .route("/song/set", post(set_active_song))
+        .route("/song/speaker", post(assign_speaker))
+        .route("/song/structure", get(get_structure))
+        .route("/song/structure", put(set_structure))
+        .route("/song/timing", put(set_line_timings))
+        .route("/song/timing/lrc", post(import_lrc))
+        .route("/song/style", put(set_song_style))
+        .route("/song/vamp", post(add_vamp))
+        .route("/song/vamp", delete(delete_vamp))
+        .route("/song/vamp/break", post(break_vamp))
         .route("/songs", get(get_all_songs))
         .merge(sse_router)
         .layer(cors_layer)
@@ -0,0 +1,155 @@
+use crate::types::Span;
+
+/// Bidirectional overrides and isolates, which can make a line render as something else.
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Strips characters that have no business on the displays, keeping newlines.
+pub fn sanitize(source: &str) -> String {
+    source
+        .chars()
+        .filter(|&c| c == '\n' || !(c.is_control() || is_bidi_control(c)))
+        .collect()
+}
+
+#[derive(Default)]
+struct Builder {
+    spans: Vec<Span>,
+    text: String,
+    bold: bool,
+    italic: bool,
+    emphasis: bool,
+}
+
+impl Builder {
+    fn flush(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+
+        self.spans.push(Span::Text {
+            text: std::mem::take(&mut self.text),
+            bold: self.bold,
+            italic: self.italic,
+            emphasis: self.emphasis,
+        });
+    }
+
+    fn toggle(&mut self, marker: char) {
+        self.flush();
+        match marker {
+            '*' => self.bold = !self.bold,
+            '_' => self.italic = !self.italic,
+            _ => self.emphasis = !self.emphasis,
+        }
+    }
+}
+
+/// Parses a line into styled spans.
+///
+/// - `*bold*`
+/// - `_italic_`, e.g. for backing vocals
+/// - `~emphasis~`
+/// - `//` or a newline breaks the line
+/// - `\` makes the next character literal, e.g. `\*`
+///
+/// Markers can be nested but must be closed within the line. The displays only
+/// ever get the parsed spans, so no markup reaches the browser as anything but text.
+pub fn parse_markup(source: &str) -> Result<Vec<Span>, &'static str> {
+    let source = sanitize(source);
+    let mut builder = Builder::default();
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => builder.text.push(chars.next().unwrap_or('\\')),
+            '/' if chars.peek() == Some(&'/') => {
+                chars.next();
+                builder.flush();
+                builder.spans.push(Span::Break);
+            }
+            '\n' => {
+                builder.flush();
+                builder.spans.push(Span::Break);
+            }
+            '*' | '_' | '~' => builder.toggle(c),
+            c => builder.text.push(c),
+        }
+    }
+
+    if builder.bold {
+        return Err("Unclosed * (bold) in line");
+    }
+    if builder.italic {
+        return Err("Unclosed _ (italic) in line");
+    }
+    if builder.emphasis {
+        return Err("Unclosed ~ (emphasis) in line");
+    }
+
+    builder.flush();
+
+    Ok(builder.spans)
+}
+
+/// Parses a line, falling back to a single unstyled span for lines stored
+/// before markup existed that don't parse.
+pub fn spans_or_plain(source: &str) -> Vec<Span> {
+    parse_markup(source).unwrap_or_else(|_| {
+        vec![Span::Text {
+            text: sanitize(source),
+            bold: false,
+            italic: false,
+            emphasis: false,
+        }]
+    })
+}
+
+/// The text of the spans without any styling, for exports and plain text displays.
+pub fn plain_text(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| match span {
+            Span::Text { text, .. } => text.as_str(),
+            Span::Break => "\n",
+        })
+        .collect()
+}
+
+/// Writes spans back as markup, the inverse of `parse_markup`.
+pub fn to_markup(spans: &[Span]) -> String {
+    let mut markup = String::new();
+
+    for span in spans {
+        let Span::Text {
+            text,
+            bold,
+            italic,
+            emphasis,
+        } = span
+        else {
+            markup.push_str("//");
+            continue;
+        };
+
+        let markers = [(*bold, '*'), (*italic, '_'), (*emphasis, '~')]
+            .into_iter()
+            .filter_map(|(set, marker)| set.then_some(marker))
+            .collect::<String>();
+
+        markup.push_str(&markers);
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            // A single slash is text, two break the line, even across spans
+            let breaks = c == '/' && matches!(chars.peek(), Some('/') | None);
+            if matches!(c, '\\' | '*' | '_' | '~' | '\n') || breaks {
+                markup.push('\\');
+            }
+            markup.push(c);
+        }
+        markup.extend(markers.chars().rev());
+    }
+
+    markup
+}
@@ -0,0 +1,58 @@
+use crate::{
+    types::{Announcement, Countdown, Frame},
+    Store,
+};
+
+/// Everything competing for the audience displays, highest priority first.
+#[derive(Debug, Default)]
+pub struct Output {
+    pub announcement: Option<Announcement>,
+    pub countdown: Option<Countdown>,
+    /// The current line of the active song, shown once nothing overrides it.
+    pub song_line: String,
+    /// The visible lines of the active song, whatever overrides them.
+    pub frame: Frame,
+}
+
+impl Output {
+    /// Whether something is showing on the displays instead of the song.
+    pub fn is_overridden(&self) -> bool {
+        self.announcement.is_some() || self.countdown.is_some()
+    }
+
+    /// The text the displays should be showing right now.
+    pub fn audience_text(&self) -> &str {
+        match (&self.announcement, &self.countdown) {
+            (Some(announcement), _) => &announcement.text,
+            // Plain text displays can only show what the countdown is for
+            (None, Some(countdown)) => countdown.label.as_deref().unwrap_or_default(),
+            (None, None) => &self.song_line,
+        }
+    }
+}
+
+/// Sends the current state of the output to the displays.
+pub fn publish(state: &Store, output: &Output) {
+    let _ = state.line_ch.send(output.audience_text().to_string());
+}
+
+/// Records the current line of the active song and shows it, unless
+/// something overrides it, in which case it shows once the override clears.
+pub async fn show_song_line(state: &Store, text: String) {
+    let mut output = state.output.write().await;
+    output.song_line = text;
+
+    if !output.is_overridden() {
+        publish(state, &output);
+    }
+}
+
+/// Records the visible lines of the active song and sends them to the displays.
+///
+/// Frames describe the song alone, displays showing announcements and
+/// countdowns over it follow those channels as well.
+pub async fn show_frame(state: &Store, frame: Frame) {
+    let mut output = state.output.write().await;
+    let _ = state.frame_ch.send(frame.clone());
+    output.frame = frame;
+}
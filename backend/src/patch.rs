@@ -0,0 +1,418 @@
+use std::collections::HashSet;
+
+use axum::{extract::State, http::StatusCode, Json};
+use diesel::{
+    prelude::AsChangeset, result::Error, Connection, PgConnection, QueryDsl, QueryResult,
+    RunQueryDsl,
+};
+use pgvector::Vector;
+use serde::{Deserialize, Deserializer};
+use tracing::{error, info};
+
+use crate::{
+    controller::load_line,
+    markup::{parse_markup, sanitize},
+    preset::apply_presets,
+    schema::{block, lines},
+    structure::load_song_lines,
+    types::{Animation, LineComp, LineKind, LineStyle, Vector3},
+    Store,
+};
+
+/// Tells a field that is `null` (`Some(None)`) apart from one that is missing (`None`).
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Changes to a line. Missing fields are left untouched, `null` clears a nullable field.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct LinePatch {
+    pub line: Option<String>,
+    pub kind: Option<LineKind>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub notes: Option<Option<String>>,
+    pub hold: Option<bool>,
+    /// Moves the line to another block, and with it to the song owning that block.
+    pub block_id: Option<i32>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub speaker_id: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub preset_id: Option<Option<i32>>,
+    pub position: Option<Vector3>,
+    pub cam_position: Option<Vector3>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub cam_position_duration: Option<Option<i32>>,
+    pub cam_look_at: Option<Vector3>,
+    pub keep_n_last: Option<i32>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub rotation: Option<Option<Vector3>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub cam_rotation: Option<Option<Vector3>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub end_position: Option<Option<Vector3>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub text_position_duration: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub cam_end_position: Option<Option<Vector3>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub cam_end_look_at: Option<Option<Vector3>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub animation: Option<Option<Animation>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub font_weight: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub font_scale: Option<Option<f32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub outline_color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub outline_width: Option<Option<f32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub opacity: Option<Option<f32>>,
+}
+
+#[derive(Debug, AsChangeset, PartialEq, Default)]
+#[diesel(table_name = lines)]
+struct DbLinePatch {
+    line: Option<String>,
+    kind: Option<LineKind>,
+    notes: Option<Option<String>>,
+    hold: Option<bool>,
+    song_id: Option<i32>,
+    block_id: Option<i32>,
+    speaker_id: Option<Option<i32>>,
+    preset_id: Option<Option<i32>>,
+    position: Option<Vector>,
+    cam_position: Option<Vector>,
+    cam_position_duration: Option<Option<i32>>,
+    cam_look_at: Option<Vector>,
+    keep_n_last: Option<i32>,
+    rotation: Option<Option<Vector>>,
+    cam_rotation: Option<Option<Vector>>,
+    end_position: Option<Option<Vector>>,
+    text_position_duration: Option<Option<i32>>,
+    cam_end_position: Option<Option<Vector>>,
+    cam_end_look_at: Option<Option<Vector>>,
+    animation: Option<Option<serde_json::Value>>,
+    color: Option<Option<String>>,
+    font_weight: Option<Option<i32>>,
+    font_scale: Option<Option<f32>>,
+    outline_color: Option<Option<String>>,
+    outline_width: Option<Option<f32>>,
+    opacity: Option<Option<f32>>,
+}
+
+fn vector(value: Option<Vector3>) -> Option<Vector> {
+    value.map(Vector::from)
+}
+
+fn nullable_vector(value: Option<Option<Vector3>>) -> Option<Option<Vector>> {
+    value.map(|value| value.map(Vector::from))
+}
+
+impl LinePatch {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if let Some(line) = &self.line {
+            parse_markup(line)?;
+        }
+
+        // Only the fields being set need to be valid
+        LineStyle {
+            color: self.color.clone().flatten(),
+            font_weight: self.font_weight.flatten(),
+            font_scale: self.font_scale.flatten(),
+            outline_color: self.outline_color.clone().flatten(),
+            outline_width: self.outline_width.flatten(),
+            opacity: self.opacity.flatten(),
+        }
+        .validate()?;
+
+        if let Some(Some(animation)) = &self.animation {
+            animation.validate()?;
+        }
+        if self.keep_n_last.is_some_and(|keep| keep < 0) {
+            return Err("keep_n_last can't be negative");
+        }
+        let durations = [self.cam_position_duration, self.text_position_duration];
+        if durations.into_iter().flatten().flatten().any(|ms| ms < 0) {
+            return Err("Durations can't be negative");
+        }
+
+        Ok(())
+    }
+
+    fn into_db(self, song_id: Option<i32>) -> DbLinePatch {
+        DbLinePatch {
+            line: self.line.as_deref().map(sanitize),
+            kind: self.kind,
+            notes: self.notes,
+            hold: self.hold,
+            song_id,
+            block_id: self.block_id,
+            speaker_id: self.speaker_id,
+            preset_id: self.preset_id,
+            position: vector(self.position),
+            cam_position: vector(self.cam_position),
+            cam_position_duration: self.cam_position_duration,
+            cam_look_at: vector(self.cam_look_at),
+            keep_n_last: self.keep_n_last,
+            rotation: nullable_vector(self.rotation),
+            cam_rotation: nullable_vector(self.cam_rotation),
+            end_position: nullable_vector(self.end_position),
+            text_position_duration: self.text_position_duration,
+            cam_end_position: nullable_vector(self.cam_end_position),
+            cam_end_look_at: nullable_vector(self.cam_end_look_at),
+            animation: self.animation.map(|animation| {
+                animation.and_then(|animation| serde_json::to_value(animation).ok())
+            }),
+            color: self.color,
+            font_weight: self.font_weight,
+            font_scale: self.font_scale,
+            outline_color: self.outline_color,
+            outline_width: self.outline_width,
+            opacity: self.opacity,
+        }
+    }
+}
+
+/// Applies a validated patch to a line, returning `NotFound` for unknown lines and blocks.
+pub fn apply_patch(con: &mut PgConnection, line_id: i32, patch: LinePatch) -> QueryResult<()> {
+    let song_id = match patch.block_id {
+        Some(block_id) => Some(
+            block::table
+                .find(block_id)
+                .select(block::song_id)
+                .first::<i32>(con)?,
+        ),
+        None => None,
+    };
+
+    let changes = patch.into_db(song_id);
+
+    // Diesel refuses empty changesets, an empty patch only checks the line exists
+    if changes == DbLinePatch::default() {
+        return lines::table
+            .find(line_id)
+            .select(lines::id)
+            .first::<i32>(con)
+            .map(|_| ());
+    }
+
+    match diesel::update(lines::table.find(line_id))
+        .set(&changes)
+        .execute(con)?
+    {
+        0 => Err(Error::NotFound),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PatchLine {
+    id: i32,
+    #[serde(flatten)]
+    patch: LinePatch,
+}
+
+pub async fn patch_line(
+    State(state): State<Store>,
+    Json(body): Json<PatchLine>,
+) -> Result<Json<LineComp>, (StatusCode, &'static str)> {
+    body.patch
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let line_id = body.id;
+
+    let res = pool
+        .interact(move |con| {
+            con.transaction(|tran| {
+                apply_patch(tran, body.id, body.patch)?;
+                load_line(tran, body.id)
+            })
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(line_comp) => {
+            info!("Patched line with id: {}", line_id);
+            Ok(Json(line_comp))
+        }
+        Err(Error::NotFound) => Err((StatusCode::NOT_FOUND, "Line or block not found")),
+        Err(e) => {
+            error!("Failed to patch line {}: {}", line_id, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to patch line"))
+        }
+    }
+}
+
+/// The lines a bulk edit applies to.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum LineSelection {
+    Ids {
+        ids: Vec<i32>,
+    },
+    /// Positions of the first and last line in `LoadSong::lines`, both inclusive.
+    Range {
+        song_id: i32,
+        start: usize,
+        end: usize,
+    },
+}
+
+impl LineSelection {
+    /// The selected line ids, each once, in the order they were selected.
+    fn line_ids(&self, con: &mut PgConnection) -> QueryResult<Vec<i32>> {
+        let ids = match self {
+            LineSelection::Ids { ids } => ids.clone(),
+            LineSelection::Range {
+                song_id,
+                start,
+                end,
+            } => load_song_lines(con, *song_id)?
+                .get(*start..=*end)
+                .ok_or(Error::NotFound)?
+                .iter()
+                .map(|line| line.id)
+                .collect(),
+        };
+
+        let mut seen = HashSet::new();
+        Ok(ids.into_iter().filter(|id| seen.insert(*id)).collect())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkOperation {
+    /// Applies the same changes to every line.
+    Patch { patch: Box<LinePatch> },
+    /// Gives every line the camera of another line.
+    CopyCamera { from_line_id: i32 },
+    /// Moves the text of every line, including where it animates to.
+    Offset { offset: Vector3 },
+}
+
+fn offset(value: &Vector3, by: &Vector3) -> Vector3 {
+    Vector3 {
+        x: value.x + by.x,
+        y: value.y + by.y,
+        z: value.z + by.z,
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BulkEdit {
+    lines: LineSelection,
+    #[serde(flatten)]
+    operation: BulkOperation,
+    /// Returns the lines as they would be after the edit without saving anything.
+    #[serde(default)]
+    preview: bool,
+}
+
+fn apply_bulk(con: &mut PgConnection, body: &BulkEdit) -> QueryResult<Vec<LineComp>> {
+    let line_ids = body.lines.line_ids(con)?;
+
+    match &body.operation {
+        BulkOperation::Patch { patch } => {
+            for &line_id in &line_ids {
+                apply_patch(con, line_id, patch.as_ref().clone())?;
+            }
+        }
+        BulkOperation::CopyCamera { from_line_id } => {
+            // The camera the line shows with, which may come from its preset
+            let mut from = load_line(con, *from_line_id)?;
+            apply_presets(con, std::slice::from_mut(&mut from))?;
+            let patch = LinePatch {
+                cam_position: Some(from.cam_position),
+                cam_position_duration: Some(from.cam_position_duration),
+                cam_look_at: Some(from.cam_look_at),
+                cam_rotation: Some(from.cam_rotation),
+                cam_end_position: Some(from.cam_end_position),
+                cam_end_look_at: Some(from.cam_end_look_at),
+                ..Default::default()
+            };
+
+            for &line_id in &line_ids {
+                apply_patch(con, line_id, patch.clone())?;
+            }
+        }
+        BulkOperation::Offset { offset: by } => {
+            for &line_id in &line_ids {
+                let line_comp = load_line(con, line_id)?;
+                let patch = LinePatch {
+                    position: Some(offset(&line_comp.position, by)),
+                    end_position: line_comp
+                        .end_position
+                        .map(|end_position| Some(offset(&end_position, by))),
+                    ..Default::default()
+                };
+
+                apply_patch(con, line_id, patch)?;
+            }
+        }
+    }
+
+    line_ids
+        .into_iter()
+        .map(|line_id| load_line(con, line_id))
+        .collect()
+}
+
+/// Edits many lines in one transaction, returning them as they are after the edit.
+pub async fn bulk_edit_lines(
+    State(state): State<Store>,
+    Json(body): Json<BulkEdit>,
+) -> Result<Json<Vec<LineComp>>, (StatusCode, &'static str)> {
+    if let BulkOperation::Patch { patch } = &body.operation {
+        patch.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            let mut preview = None;
+
+            let res = con.transaction(|tran| {
+                let edited = apply_bulk(tran, &body)?;
+
+                if body.preview {
+                    // Rolls back everything the edit did
+                    preview = Some(edited);
+                    return Err(Error::RollbackTransaction);
+                }
+
+                Ok(edited)
+            });
+
+            match preview {
+                Some(edited) => Ok(edited),
+                None => res,
+            }
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(edited) => {
+            info!("Bulk edited {} lines", edited.len());
+            Ok(Json(edited))
+        }
+        Err(Error::NotFound) => Err((StatusCode::NOT_FOUND, "Line, block or range not found")),
+        Err(e) => {
+            error!("Failed to bulk edit lines: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to edit lines"))
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use axum::{extract::State, http::StatusCode, Json};
+use diesel::{
+    ExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl, SelectableHelper,
+};
+use pgvector::Vector;
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::{
+    schema::preset,
+    types::{DbPreset, LineComp, Preset},
+    Store,
+};
+
+/// Replaces the camera and text position of lines referencing a preset with the preset's.
+///
+/// Runs whenever lines are loaded, so changes to a preset reach every line using it.
+pub fn apply_presets(con: &mut PgConnection, song_lines: &mut [LineComp]) -> QueryResult<()> {
+    let preset_ids = song_lines
+        .iter()
+        .filter_map(|line| line.preset_id)
+        .collect::<Vec<_>>();
+
+    if preset_ids.is_empty() {
+        return Ok(());
+    }
+
+    let presets = preset::table
+        .select(DbPreset::as_select())
+        .filter(preset::id.eq_any(&preset_ids))
+        .load(con)?
+        .into_iter()
+        .map(|row| (row.id, Preset::from(row)))
+        .collect::<HashMap<_, _>>();
+
+    for line in song_lines {
+        let Some(preset) = line.preset_id.and_then(|id| presets.get(&id)) else {
+            continue;
+        };
+
+        if let Some(cam_position) = &preset.cam_position {
+            line.cam_position = cam_position.clone();
+        }
+        if let Some(cam_look_at) = &preset.cam_look_at {
+            line.cam_look_at = cam_look_at.clone();
+        }
+        if let Some(cam_rotation) = &preset.cam_rotation {
+            line.cam_rotation = Some(cam_rotation.clone());
+        }
+        if let Some(position) = &preset.position {
+            line.position = position.clone();
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn get_presets(
+    State(state): State<Store>,
+) -> Result<Json<Vec<Preset>>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(|con| {
+            preset::table
+                .select(DbPreset::as_select())
+                .order(preset::name.asc())
+                .load(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(presets) => Ok(Json(presets.into_iter().map(Preset::from).collect())),
+        Err(e) => {
+            error!("Failed to load presets: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to load presets"))
+        }
+    }
+}
+
+type PresetValues = (
+    String,
+    Option<Vector>,
+    Option<Vector>,
+    Option<Vector>,
+    Option<Vector>,
+);
+
+/// Checks a preset sent by a client and converts it for the database.
+fn validate(body: Preset) -> Result<PresetValues, (StatusCode, &'static str)> {
+    let name = body.name.trim().to_string();
+
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Preset name is empty"));
+    }
+
+    Ok((
+        name,
+        body.cam_position.map(Vector::from),
+        body.cam_look_at.map(Vector::from),
+        body.cam_rotation.map(Vector::from),
+        body.position.map(Vector::from),
+    ))
+}
+
+pub async fn add_preset(
+    State(state): State<Store>,
+    Json(body): Json<Preset>,
+) -> Result<Json<Preset>, (StatusCode, &'static str)> {
+    let (name, cam_position, cam_look_at, cam_rotation, position) = validate(body)?;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            diesel::insert_into(preset::table)
+                .values((
+                    preset::name.eq(name),
+                    preset::cam_position.eq(cam_position),
+                    preset::cam_look_at.eq(cam_look_at),
+                    preset::cam_rotation.eq(cam_rotation),
+                    preset::position.eq(position),
+                ))
+                .returning(DbPreset::as_returning())
+                .get_result(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(added) => {
+            info!("Added preset with id: {}", added.id);
+            Ok(Json(Preset::from(added)))
+        }
+        Err(e) => {
+            error!("Failed to add preset: {}", e);
+            Err((StatusCode::CONFLICT, "Failed to add preset"))
+        }
+    }
+}
+
+pub async fn edit_preset(
+    State(state): State<Store>,
+    Json(body): Json<Preset>,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    let id = body.id;
+    let (name, cam_position, cam_look_at, cam_rotation, position) = validate(body)?;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            diesel::update(preset::table.find(id))
+                .set((
+                    preset::name.eq(name),
+                    preset::cam_position.eq(cam_position),
+                    preset::cam_look_at.eq(cam_look_at),
+                    preset::cam_rotation.eq(cam_rotation),
+                    preset::position.eq(position),
+                ))
+                .execute(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(0) => Err((StatusCode::NOT_FOUND, "Preset not found")),
+        Ok(_) => {
+            info!("Updated preset with id: {}", id);
+            Ok(StatusCode::OK)
+        }
+        Err(e) => {
+            error!("Failed to update preset {}: {}", id, e);
+            Err((StatusCode::CONFLICT, "Failed to update preset"))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PresetRequest {
+    id: i32,
+}
+
+/// Deletes a preset. Lines using it keep their own values from then on.
+pub async fn delete_preset(
+    State(state): State<Store>,
+    Json(body): Json<PresetRequest>,
+) -> StatusCode {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| diesel::delete(preset::table.find(body.id)).execute(con))
+        .await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        error!("Failed to delete preset with id: {}", body.id);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    info!("Deleted preset with id: {}", body.id);
+
+    StatusCode::OK
+}
@@ -0,0 +1,412 @@
+use std::{
+    convert::Infallible,
+    sync::{Arc, OnceLock},
+};
+
+use async_stream::stream;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+};
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{
+        fontdb::{self, Database},
+        Options, Tree,
+    },
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info};
+
+use crate::{
+    fit::wrap_spans,
+    output::Output,
+    types::{FrameLine, LineStyle, Span},
+    Store,
+};
+
+const MAX_WIDTH: u32 = 3840;
+const MAX_HEIGHT: u32 = 2160;
+const MAX_FONT_SIZE: f32 = 512.0;
+
+/// Rows of text are this many font sizes apart.
+const LINE_HEIGHT: f32 = 1.25;
+
+/// Average width of a character in font sizes, to wrap rows without measuring the text.
+const CHAR_WIDTH: f32 = 0.55;
+
+const DEFAULT_COLOR: &str = "#ffffff";
+const DEFAULT_OUTLINE_COLOR: &str = "#000000";
+
+const STREAM_BOUNDARY: &str = "frame";
+
+/// How a rendered subtitle looks, the same for every output format.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RenderOptions {
+    #[serde(default = "default_width")]
+    width: u32,
+    #[serde(default = "default_height")]
+    height: u32,
+    /// Font family, `RENDER_FONT` or the system's sans-serif when not set.
+    font: Option<String>,
+    /// Text size in pixels, lines scale it with their `font_scale`.
+    #[serde(default = "default_font_size")]
+    font_size: f32,
+    /// Share of the width and height kept free at every edge.
+    #[serde(default = "default_safe_area")]
+    safe_area: f32,
+}
+
+fn default_width() -> u32 {
+    1920
+}
+
+fn default_height() -> u32 {
+    1080
+}
+
+fn default_font_size() -> f32 {
+    64.0
+}
+
+fn default_safe_area() -> f32 {
+    0.05
+}
+
+impl RenderOptions {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)> {
+        if !(1..=MAX_WIDTH).contains(&self.width) || !(1..=MAX_HEIGHT).contains(&self.height) {
+            return Err((StatusCode::BAD_REQUEST, "Image size out of range"));
+        }
+        if !(self.font_size > 0.0 && self.font_size <= MAX_FONT_SIZE) {
+            return Err((StatusCode::BAD_REQUEST, "Font size out of range"));
+        }
+        if !(0.0..0.5).contains(&self.safe_area) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Safe area must be between 0 and 0.5",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn font(&self) -> String {
+        self.font
+            .clone()
+            .or_else(|| std::env::var("RENDER_FONT").ok())
+            .unwrap_or_else(|| "sans-serif".to_string())
+    }
+}
+
+/// Fonts of the system, plus any in `RENDER_FONT_DIR`. Loaded on first use.
+fn fonts() -> Arc<Database> {
+    static FONTS: OnceLock<Arc<Database>> = OnceLock::new();
+
+    FONTS
+        .get_or_init(|| {
+            let mut db = Database::new();
+            db.load_system_fonts();
+            if let Ok(dir) = std::env::var("RENDER_FONT_DIR") {
+                db.load_fonts_dir(dir);
+            }
+
+            // Minimal systems rarely have the font fontdb expects for sans-serif
+            let query = fontdb::Query {
+                families: &[fontdb::Family::SansSerif],
+                ..Default::default()
+            };
+            if db.query(&query).is_none() {
+                let fallback = db
+                    .faces()
+                    .find_map(|face| face.families.first())
+                    .map(|(family, _)| family.clone());
+                if let Some(family) = fallback {
+                    db.set_sans_serif_family(family);
+                }
+            }
+
+            info!("Loaded {} font faces for rendering", db.len());
+            Arc::new(db)
+        })
+        .clone()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// What the displays show, an announcement or countdown over the song as a single line.
+fn shown_lines(output: &Output) -> Vec<FrameLine> {
+    if !output.is_overridden() {
+        return output.frame.lines.clone();
+    }
+
+    let text = output.audience_text();
+    let color = match (&output.announcement, &output.countdown) {
+        (Some(announcement), _) => announcement.color.clone(),
+        (None, Some(countdown)) => countdown.color.clone(),
+        (None, None) => None,
+    };
+
+    match text.is_empty() {
+        true => Vec::new(),
+        false => vec![FrameLine {
+            text: text.to_string(),
+            current: true,
+            style: LineStyle {
+                color,
+                ..Default::default()
+            },
+            ..Default::default()
+        }],
+    }
+}
+
+/// Splits a line into the rows it takes on screen, at most `max_chars` wide.
+fn rows(line: &FrameLine, max_chars: usize) -> Vec<Vec<Span>> {
+    if line.spans.is_empty() {
+        let text = Span::Text {
+            text: line.text.clone(),
+            bold: false,
+            italic: false,
+            emphasis: false,
+        };
+        return wrap_spans(&[text], max_chars);
+    }
+
+    wrap_spans(&line.spans, max_chars)
+}
+
+fn tspan(span: &Span) -> String {
+    let Span::Text {
+        text,
+        bold,
+        italic,
+        emphasis,
+    } = span
+    else {
+        return String::new();
+    };
+
+    let mut attributes = String::new();
+    if *bold {
+        attributes.push_str(r#" font-weight="bold""#);
+    }
+    if *italic {
+        attributes.push_str(r#" font-style="italic""#);
+    }
+    if *emphasis {
+        attributes.push_str(r#" text-decoration="underline""#);
+    }
+
+    format!("<tspan{attributes}>{}</tspan>", escape(text))
+}
+
+/// Renders lines as a lower third on a transparent background, the last line
+/// at the bottom of the safe area and earlier ones above it.
+///
+/// Rows are wrapped to the width of the safe area. Rows that would reach above
+/// it are left out, starting with the earliest line.
+pub fn render_svg(lines: &[FrameLine], options: &RenderOptions) -> String {
+    let width = options.width as f32;
+    let height = options.height as f32;
+    let center = width / 2.0;
+    let top = height * options.safe_area;
+    let safe_width = width * (1.0 - 2.0 * options.safe_area);
+
+    // Laid out bottom up, so the current line never leaves the safe area
+    let mut baseline = height * (1.0 - options.safe_area);
+    let mut texts = Vec::new();
+
+    'lines: for line in lines.iter().rev() {
+        let style = &line.style;
+        let size = options.font_size * style.font_scale.unwrap_or(1.0);
+        let max_chars = (safe_width / (size * CHAR_WIDTH)) as usize;
+
+        for row in rows(line, max_chars).iter().rev() {
+            // Room for descenders below the bottom row
+            baseline -= size * (LINE_HEIGHT - 1.0);
+
+            if baseline - size < top {
+                break 'lines;
+            }
+
+            let mut attributes = format!(
+                r#"x="{center}" y="{baseline}" font-size="{size}" fill="{}""#,
+                escape(style.color.as_deref().unwrap_or(DEFAULT_COLOR)),
+            );
+            if let Some(font_weight) = style.font_weight {
+                attributes.push_str(&format!(r#" font-weight="{font_weight}""#));
+            }
+            if let Some(opacity) = style.opacity {
+                attributes.push_str(&format!(r#" opacity="{opacity}""#));
+            }
+            if let Some(outline_width) = style.outline_width.filter(|&width| width > 0.0) {
+                attributes.push_str(&format!(
+                    r#" stroke="{}" stroke-width="{outline_width}" stroke-linejoin="round" paint-order="stroke""#,
+                    escape(
+                        style
+                            .outline_color
+                            .as_deref()
+                            .unwrap_or(DEFAULT_OUTLINE_COLOR)
+                    ),
+                ));
+            }
+
+            let spans = row.iter().map(tspan).collect::<String>();
+            texts.push(format!(r#"<text {attributes}>{spans}</text>"#));
+
+            baseline -= size;
+        }
+    }
+
+    texts.reverse();
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}"><g font-family="{}" text-anchor="middle">{}</g></svg>"#,
+        options.width,
+        options.height,
+        options.width,
+        options.height,
+        escape(&options.font()),
+        texts.concat(),
+    )
+}
+
+/// Rasterizes a rendered subtitle into a PNG with alpha.
+pub fn render_png(svg: &str, options: &RenderOptions) -> Result<Vec<u8>, &'static str> {
+    let usvg_options = Options {
+        fontdb: fonts(),
+        ..Default::default()
+    };
+
+    let tree = Tree::from_str(svg, &usvg_options).map_err(|e| {
+        error!("Failed to parse rendered subtitle: {}", e);
+        "Failed to render subtitle"
+    })?;
+
+    let mut pixmap =
+        Pixmap::new(options.width, options.height).ok_or("Failed to allocate the image")?;
+    resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
+
+    pixmap.encode_png().map_err(|e| {
+        error!("Failed to encode subtitle: {}", e);
+        "Failed to encode subtitle"
+    })
+}
+
+async fn current_svg(state: &Store, options: &RenderOptions) -> String {
+    let output = state.output.read().await;
+    render_svg(&shown_lines(&output), options)
+}
+
+async fn current_png(state: &Store, options: &RenderOptions) -> Result<Vec<u8>, &'static str> {
+    let svg = current_svg(state, options).await;
+    let options = options.clone();
+
+    tokio::task::spawn_blocking(move || render_png(&svg, &options))
+        .await
+        .unwrap()
+}
+
+pub async fn get_render_svg(
+    State(state): State<Store>,
+    Query(options): Query<RenderOptions>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    options.validate()?;
+
+    let svg = current_svg(&state, &options).await;
+
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response())
+}
+
+pub async fn get_render_png(
+    State(state): State<Store>,
+    Query(options): Query<RenderOptions>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    options.validate()?;
+
+    let png = current_png(&state, &options)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(([(header::CONTENT_TYPE, "image/png")], png).into_response())
+}
+
+/// A new PNG every time the displays change, as a `multipart/x-mixed-replace`
+/// stream like MJPEG but keeping the alpha channel.
+pub async fn get_render_stream(
+    State(state): State<Store>,
+    Query(options): Query<RenderOptions>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    options.validate()?;
+
+    let mut frames = state.frame_ch.subscribe();
+    let mut line = state.line_ch.subscribe();
+
+    let body = stream! {
+        loop {
+            match current_png(&state, &options).await {
+                Ok(png) => {
+                    let part = format!(
+                        "--{STREAM_BOUNDARY}\r\nContent-Type: image/png\r\nContent-Length: {}\r\n\r\n",
+                        png.len()
+                    );
+                    yield Ok::<_, Infallible>(Bytes::from(part));
+                    yield Ok(Bytes::from(png));
+                    yield Ok(Bytes::from_static(b"\r\n"));
+                }
+                Err(e) => error!("Failed to render stream frame: {}", e),
+            }
+
+            // Either one changing changes what the displays show
+            let changed = tokio::select! {
+                res = frames.recv() => res.map(|_| ()),
+                res = line.recv() => res.map(|_| ()),
+            };
+            if let Err(RecvError::Closed) = changed {
+                break;
+            }
+        }
+    };
+
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            format!("multipart/x-mixed-replace; boundary={STREAM_BOUNDARY}"),
+        )],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+/// A transparent page showing the current lines, for streaming software's browser sources.
+pub async fn get_render_page(
+    Query(options): Query<RenderOptions>,
+) -> Result<Html<String>, (StatusCode, &'static str)> {
+    options.validate()?;
+
+    let page = include_str!("render.html")
+        .replace("{font}", &escape(&options.font()))
+        .replace("{font_size}", &options.font_size.to_string())
+        .replace("{safe_area}", &(options.safe_area * 100.0).to_string())
+        .replace("{color}", DEFAULT_COLOR)
+        .replace("{outline_color}", DEFAULT_OUTLINE_COLOR);
+
+    Ok(Html(page))
+}
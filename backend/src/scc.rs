@@ -0,0 +1,511 @@
+use std::fmt::Write;
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use ts_rs::TS;
+
+use crate::{
+    camera::DEFAULT_LINE_MS, captions::Caption, structure::load_song_lines, types::LineComp, Store,
+};
+
+/// Characters a CEA-608 row holds.
+pub const MAX_ROW_CHARS: usize = 32;
+
+/// Rows a pop-on caption can take, longer captions are split over several.
+pub const MAX_ROWS: usize = 4;
+
+/// Frames behind a caption shown more than this late are reported.
+const MAX_LATE_FRAMES: i64 = 15;
+
+// Control codes for channel 1, before parity
+const RESUME_CAPTION_LOADING: (u8, u8) = (0x14, 0x20);
+const ERASE_NON_DISPLAYED_MEMORY: (u8, u8) = (0x14, 0x2e);
+const END_OF_CAPTION: (u8, u8) = (0x14, 0x2f);
+const ERASE_DISPLAYED_MEMORY: (u8, u8) = (0x14, 0x2c);
+
+/// Preamble address codes of rows 1 to 15, with the indent added to the second byte.
+const ROW_ADDRESSES: [(u8, u8); 15] = [
+    (0x11, 0x40),
+    (0x11, 0x60),
+    (0x12, 0x40),
+    (0x12, 0x60),
+    (0x15, 0x40),
+    (0x15, 0x60),
+    (0x16, 0x40),
+    (0x16, 0x60),
+    (0x17, 0x40),
+    (0x17, 0x60),
+    (0x10, 0x40),
+    (0x13, 0x40),
+    (0x13, 0x60),
+    (0x14, 0x40),
+    (0x14, 0x60),
+];
+
+/// Basic characters that differ from ASCII.
+const BASIC_REPLACED: [(char, u8); 10] = [
+    ('á', 0x2a),
+    ('é', 0x5c),
+    ('í', 0x5e),
+    ('ó', 0x5f),
+    ('ú', 0x60),
+    ('ç', 0x7b),
+    ('÷', 0x7c),
+    ('Ñ', 0x7d),
+    ('ñ', 0x7e),
+    ('█', 0x7f),
+];
+
+/// Special characters, from 0x30 after 0x11. 0x39 is a transparent space.
+const SPECIAL: [char; 16] = [
+    '®', '°', '½', '¿', '™', '¢', '£', '♪', 'à', '\0', 'è', 'â', 'ê', 'î', 'ô', 'û',
+];
+
+/// Extended characters, from 0x20 after 0x12 and 0x13, with the basic
+/// character decoders without them show instead.
+const EXTENDED: [[(char, char); 32]; 2] = [
+    [
+        ('Á', 'A'),
+        ('É', 'E'),
+        ('Ó', 'O'),
+        ('Ú', 'U'),
+        ('Ü', 'U'),
+        ('ü', 'u'),
+        ('‘', '\''),
+        ('¡', '!'),
+        ('*', '\''),
+        ('’', '\''),
+        ('—', '-'),
+        ('©', 'c'),
+        ('℠', ' '),
+        ('•', '.'),
+        ('“', '"'),
+        ('”', '"'),
+        ('À', 'A'),
+        ('Â', 'A'),
+        ('Ç', 'C'),
+        ('È', 'E'),
+        ('Ê', 'E'),
+        ('Ë', 'E'),
+        ('ë', 'e'),
+        ('Î', 'I'),
+        ('Ï', 'I'),
+        ('ï', 'i'),
+        ('Ô', 'O'),
+        ('Ù', 'U'),
+        ('ù', 'u'),
+        ('Û', 'U'),
+        ('«', '"'),
+        ('»', '"'),
+    ],
+    [
+        ('Ã', 'A'),
+        ('ã', 'a'),
+        ('Í', 'I'),
+        ('Ì', 'I'),
+        ('ì', 'i'),
+        ('Ò', 'O'),
+        ('ò', 'o'),
+        ('Õ', 'O'),
+        ('õ', 'o'),
+        ('{', '('),
+        ('}', ')'),
+        ('\\', '/'),
+        ('^', '\''),
+        ('_', '-'),
+        ('|', '!'),
+        ('~', '-'),
+        ('Ä', 'A'),
+        ('ä', 'a'),
+        ('Ö', 'O'),
+        ('ö', 'o'),
+        ('ß', 's'),
+        ('¥', 'Y'),
+        ('¤', 'C'),
+        ('¦', '!'),
+        ('Å', 'A'),
+        ('å', 'a'),
+        ('Ø', 'O'),
+        ('ø', 'o'),
+        ('┌', '+'),
+        ('┐', '+'),
+        ('└', '+'),
+        ('┘', '+'),
+    ],
+];
+
+/// A line of a caption that CEA-608 can't show as written.
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct SccIssue {
+    pub line_id: Option<i32>,
+    pub start_ms: i64,
+    pub text: String,
+    pub message: String,
+}
+
+/// Sets the top bit so every byte has an odd number of bits set.
+fn parity(byte: u8) -> u8 {
+    match byte.count_ones() % 2 {
+        0 => byte | 0x80,
+        _ => byte,
+    }
+}
+
+fn word((first, second): (u8, u8)) -> u16 {
+    u16::from_be_bytes([parity(first), parity(second)])
+}
+
+/// How a character is sent, `None` if it can't be.
+enum Encoded {
+    Basic(u8),
+    /// A control pair, sent after the basic character older decoders show instead.
+    Pair(Option<u8>, (u8, u8)),
+}
+
+fn basic(c: char) -> Option<u8> {
+    if let Some((_, byte)) = BASIC_REPLACED.iter().find(|(replaced, _)| *replaced == c) {
+        return Some(*byte);
+    }
+
+    match c {
+        '*' | '\\' | '^' | '_' | '`' | '{' | '|' | '}' | '~' => None,
+        ' '..='\x7e' => Some(c as u8),
+        _ => None,
+    }
+}
+
+fn encode_char(c: char) -> Option<Encoded> {
+    if let Some(byte) = basic(c) {
+        return Some(Encoded::Basic(byte));
+    }
+
+    if let Some(i) = SPECIAL
+        .iter()
+        .position(|&special| special == c && c != '\0')
+    {
+        return Some(Encoded::Pair(None, (0x11, 0x30 + i as u8)));
+    }
+
+    EXTENDED.iter().enumerate().find_map(|(set, chars)| {
+        let i = chars.iter().position(|(extended, _)| *extended == c)?;
+        Some(Encoded::Pair(
+            basic(chars[i].1),
+            (0x12 + set as u8, 0x20 + i as u8),
+        ))
+    })
+}
+
+/// Wraps text into rows of at most `MAX_ROW_CHARS` characters, at spaces where
+/// possible. Returns whether a word had to be cut.
+pub fn wrap_rows(text: &str, max_chars: usize) -> (Vec<String>, bool) {
+    let mut rows = Vec::new();
+    let mut cut = false;
+
+    for line in text.lines() {
+        let mut row = String::new();
+
+        for word in line.split_whitespace() {
+            let mut word = word.chars().collect::<Vec<_>>();
+
+            let row_len = row.chars().count();
+            if row_len > 0 && row_len + 1 + word.len() > max_chars {
+                rows.push(std::mem::take(&mut row));
+            }
+
+            while word.len() > max_chars {
+                cut = true;
+                if !row.is_empty() {
+                    rows.push(std::mem::take(&mut row));
+                }
+                rows.push(word.drain(..max_chars).collect());
+            }
+
+            if !row.is_empty() {
+                row.push(' ');
+            }
+            row.extend(word);
+        }
+
+        if !row.is_empty() {
+            rows.push(row);
+        }
+    }
+
+    (rows, cut)
+}
+
+/// SMPTE timecode of a frame, at 29.97 frames per second without drop frames.
+fn timecode(frame: i64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        frame / (30 * 3600),
+        frame / (30 * 60) % 60,
+        frame / 30 % 60,
+        frame % 30
+    )
+}
+
+fn ms_to_frame(ms: i64) -> i64 {
+    ms * 30 / 1001
+}
+
+/// Basic characters go two to a byte pair.
+fn push_basic(words: &mut Vec<u16>, pending: &mut Option<u8>, byte: u8) {
+    match pending.take() {
+        Some(previous) => words.push(word((previous, byte))),
+        None => *pending = Some(byte),
+    }
+}
+
+/// Pads a lone basic character, control pairs must start a new byte pair.
+fn flush_basic(words: &mut Vec<u16>, pending: &mut Option<u8>) {
+    if let Some(previous) = pending.take() {
+        words.push(word((previous, 0)));
+    }
+}
+
+/// The byte pairs loading `rows` into non-displayed memory, bottom aligned and centered.
+fn load_caption(rows: &[String], caption: &Caption, issues: &mut Vec<SccIssue>) -> Vec<u16> {
+    let mut words = vec![
+        word(RESUME_CAPTION_LOADING),
+        word(RESUME_CAPTION_LOADING),
+        word(ERASE_NON_DISPLAYED_MEMORY),
+        word(ERASE_NON_DISPLAYED_MEMORY),
+    ];
+
+    let first_row = ROW_ADDRESSES.len() - rows.len();
+
+    for (i, row) in rows.iter().enumerate() {
+        let column = (MAX_ROW_CHARS - row.chars().count()) / 2;
+        let (first, second) = ROW_ADDRESSES[first_row + i];
+        let address = (first, second + 0x10 + (column / 4 * 2) as u8);
+        words.extend([word(address), word(address)]);
+
+        let offset = column % 4;
+        if offset > 0 {
+            let tab_offset = (0x17, 0x20 + offset as u8);
+            words.extend([word(tab_offset), word(tab_offset)]);
+        }
+
+        let mut pending = None;
+
+        for c in row.chars() {
+            match encode_char(c) {
+                Some(Encoded::Basic(byte)) => push_basic(&mut words, &mut pending, byte),
+                Some(Encoded::Pair(fallback, pair)) => {
+                    if let Some(fallback) = fallback {
+                        push_basic(&mut words, &mut pending, fallback);
+                    }
+                    flush_basic(&mut words, &mut pending);
+                    words.extend([word(pair), word(pair)]);
+                }
+                None => {
+                    let message = format!("'{c}' can't be shown in CEA-608, left out");
+                    let reported = issues.iter().any(|issue| {
+                        issue.start_ms == caption.start_ms && issue.message == message
+                    });
+                    if !reported {
+                        issues.push(SccIssue {
+                            line_id: caption.line_id,
+                            start_ms: caption.start_ms,
+                            text: caption.text.clone(),
+                            message,
+                        });
+                    }
+                }
+            }
+        }
+
+        flush_basic(&mut words, &mut pending);
+    }
+
+    words.extend([word(END_OF_CAPTION), word(END_OF_CAPTION)]);
+
+    words
+}
+
+/// Encodes captions as pop-on captions on channel 1 of an `.scc` file.
+///
+/// Captions are loaded ahead so they show at their start, one byte pair per
+/// frame, and cleared at their end unless the next caption replaces them.
+pub fn encode_scc(captions: &[Caption]) -> (String, Vec<SccIssue>) {
+    let mut issues = Vec::new();
+    let mut events: Vec<(i64, Vec<u16>)> = Vec::new();
+    let mut free_frame = 0;
+
+    for (i, caption) in captions.iter().enumerate() {
+        let (rows, cut) = wrap_rows(&caption.text, MAX_ROW_CHARS);
+        if cut {
+            issues.push(SccIssue {
+                line_id: caption.line_id,
+                start_ms: caption.start_ms,
+                text: caption.text.clone(),
+                message: format!("A word is longer than {MAX_ROW_CHARS} characters and was cut"),
+            });
+        }
+        if rows.is_empty() {
+            continue;
+        }
+
+        // Captions too tall for the screen take turns in equal parts of the time
+        let parts = rows.chunks(MAX_ROWS).collect::<Vec<_>>();
+        let part_ms = (caption.end_ms - caption.start_ms) / parts.len() as i64;
+
+        for (part, part_rows) in parts.iter().enumerate() {
+            let start_ms = caption.start_ms + part_ms * part as i64;
+            let words = load_caption(part_rows, caption, &mut issues);
+
+            // The end of caption code lands on the start frame
+            let wanted = ms_to_frame(start_ms) - (words.len() as i64 - 1);
+            let frame = wanted.max(free_frame);
+            if frame - wanted > MAX_LATE_FRAMES {
+                issues.push(SccIssue {
+                    line_id: caption.line_id,
+                    start_ms: caption.start_ms,
+                    text: caption.text.clone(),
+                    message: format!("Shows {} frames late, cues are too close", frame - wanted),
+                });
+            }
+
+            free_frame = frame + words.len() as i64;
+            events.push((frame, words));
+        }
+
+        let replaced = captions
+            .get(i + 1)
+            .is_some_and(|next| next.start_ms <= caption.end_ms);
+        if !replaced {
+            let frame = ms_to_frame(caption.end_ms).max(free_frame);
+            free_frame = frame + 2;
+            events.push((
+                frame,
+                vec![word(ERASE_DISPLAYED_MEMORY), word(ERASE_DISPLAYED_MEMORY)],
+            ));
+        }
+    }
+
+    let mut scc = "Scenario_V1.0\n".to_string();
+    for (frame, words) in events {
+        let words = words
+            .iter()
+            .map(|word| format!("{word:04x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = write!(scc, "\n{}\t{}\n", timecode(frame), words);
+    }
+
+    (scc, issues)
+}
+
+/// How long it takes to load the first screen of a caption before it shows.
+fn load_ms(caption: &Caption) -> i64 {
+    let (rows, _) = wrap_rows(&caption.text, MAX_ROW_CHARS);
+    let rows = &rows[..rows.len().min(MAX_ROWS)];
+
+    // The end of caption code lands on the start frame, the rest go before it
+    let frames = load_caption(rows, caption, &mut Vec::new()).len() as i64 - 1;
+    (frames * 1001 + 29) / 30
+}
+
+/// Captions for a song on the nominal timeline, a line every `line_ms`.
+///
+/// Stage directions leave the previous line showing, like on the displays.
+/// The timeline starts once the first line has had time to load, so it isn't
+/// shown late.
+pub fn song_captions(song_lines: &[LineComp], line_ms: u32) -> Vec<Caption> {
+    let mut captions: Vec<Caption> = Vec::new();
+
+    for (index, line) in song_lines.iter().enumerate() {
+        let Some(text) = line.audience_text() else {
+            continue;
+        };
+        let cued_ms = index as i64 * line_ms as i64;
+
+        if let Some(previous) = captions
+            .last_mut()
+            .filter(|caption| caption.end_ms > cued_ms)
+        {
+            previous.end_ms = cued_ms;
+        }
+
+        if !text.trim().is_empty() {
+            captions.push(Caption {
+                start_ms: cued_ms,
+                end_ms: song_lines.len() as i64 * line_ms as i64,
+                text,
+                line_id: Some(line.id),
+            });
+        }
+    }
+
+    let lead_ms = captions.first().map_or(0, load_ms);
+    for caption in &mut captions {
+        caption.start_ms += lead_ms;
+        caption.end_ms += lead_ms;
+    }
+
+    captions
+}
+
+/// The `.scc` file, or the lines it can't show as written when `report` is set.
+pub fn scc_response(captions: &[Caption], report: bool, filename: &str) -> Response {
+    let (scc, issues) = encode_scc(captions);
+
+    if report {
+        return Json(issues).into_response();
+    }
+
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=us-ascii".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        scc,
+    )
+        .into_response()
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SongSccRequest {
+    id: i32,
+    line_ms: Option<u32>,
+    #[serde(default)]
+    report: bool,
+}
+
+pub async fn get_song_scc(
+    State(state): State<Store>,
+    Query(req): Query<SongSccRequest>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    let line_ms = req.line_ms.unwrap_or(DEFAULT_LINE_MS).max(1);
+
+    let pool = state.pool.get().await.unwrap();
+
+    let song_lines = pool
+        .interact(move |con| load_song_lines(con, req.id))
+        .await
+        .unwrap()
+        .map_err(|e| {
+            error!("Failed to load lines of song {}: {}", req.id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load song")
+        })?;
+
+    Ok(scc_response(
+        &song_captions(&song_lines, line_ms),
+        req.report,
+        &format!("song_{}.scc", req.id),
+    ))
+}
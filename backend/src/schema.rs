@@ -2,8 +2,16 @@
 
 pub mod sql_types {
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
-    #[diesel(postgres_type(name = "animation"))]
-    pub struct Animation;
+    #[diesel(postgres_type(name = "cue_event"))]
+    pub struct CueEvent;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "cue_source"))]
+    pub struct CueSource;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "line_kind"))]
+    pub struct LineKind;
 }
 
 diesel::table! {
@@ -23,7 +31,62 @@ diesel::table! {
 diesel::table! {
     use diesel::sql_types::*;
     use pgvector::sql_types::*;
-    use super::sql_types::Animation;
+
+    block (id) {
+        id -> Int4,
+        song_id -> Int4,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    character (id) {
+        id -> Int4,
+        name -> Text,
+        color -> Nullable<Text>,
+        position_offset -> Vector,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+    use super::sql_types::CueEvent;
+    use super::sql_types::CueSource;
+
+    cue_log (id) {
+        id -> Int4,
+        created_at -> Timestamptz,
+        event -> CueEvent,
+        source -> CueSource,
+        operator -> Nullable<Text>,
+        song_id -> Nullable<Int4>,
+        song_name -> Nullable<Text>,
+        line_index -> Nullable<Int4>,
+        line -> Nullable<Text>,
+        skips -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    cue_state (id) {
+        id -> Int4,
+        song_id -> Int4,
+        line -> Int4,
+        broken_vamp_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+    use super::sql_types::LineKind;
 
     lines (id) {
         id -> Int4,
@@ -35,12 +98,63 @@ diesel::table! {
         cam_look_at -> Vector,
         keep_n_last -> Int4,
         rotation -> Nullable<Vector>,
-        text_animation -> Nullable<Animation>,
         text_position_duration -> Nullable<Int4>,
         cam_rotation -> Nullable<Vector>,
         end_position -> Nullable<Vector>,
         cam_end_position -> Nullable<Vector>,
         cam_end_look_at -> Nullable<Vector>,
+        kind -> LineKind,
+        notes -> Nullable<Text>,
+        hold -> Bool,
+        block_id -> Int4,
+        color -> Nullable<Text>,
+        font_weight -> Nullable<Int4>,
+        font_scale -> Nullable<Float4>,
+        outline_color -> Nullable<Text>,
+        outline_width -> Nullable<Float4>,
+        opacity -> Nullable<Float4>,
+        speaker_id -> Nullable<Int4>,
+        animation -> Nullable<Jsonb>,
+        preset_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    output_rule (id) {
+        id -> Int4,
+        name -> Text,
+        max_chars -> Int4,
+        max_rows -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    preset (id) {
+        id -> Int4,
+        name -> Text,
+        cam_position -> Nullable<Vector>,
+        cam_look_at -> Nullable<Vector>,
+        cam_rotation -> Nullable<Vector>,
+        position -> Nullable<Vector>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    section (id) {
+        id -> Int4,
+        song_id -> Int4,
+        name -> Text,
+        line_id -> Int4,
+        occurrence -> Int4,
     }
 }
 
@@ -51,13 +165,79 @@ diesel::table! {
     song (id) {
         id -> Int4,
         name -> Text,
+        color -> Nullable<Text>,
+        font_weight -> Nullable<Int4>,
+        font_scale -> Nullable<Float4>,
+        outline_color -> Nullable<Text>,
+        outline_width -> Nullable<Float4>,
+        opacity -> Nullable<Float4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    song_block (id) {
+        id -> Int4,
+        song_id -> Int4,
+        block_id -> Int4,
+        position -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    vamp (id) {
+        id -> Int4,
+        song_id -> Int4,
+        start_line_id -> Int4,
+        end_line_id -> Int4,
+        occurrence -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    word_timing (id) {
+        id -> Int4,
+        line_id -> Int4,
+        position -> Int4,
+        text -> Text,
+        start_ms -> Int4,
+        end_ms -> Nullable<Int4>,
     }
 }
 
+diesel::joinable!(block -> song (song_id));
+diesel::joinable!(cue_log -> song (song_id));
+diesel::joinable!(lines -> block (block_id));
+diesel::joinable!(lines -> character (speaker_id));
+diesel::joinable!(lines -> preset (preset_id));
 diesel::joinable!(lines -> song (song_id));
+diesel::joinable!(section -> lines (line_id));
+diesel::joinable!(section -> song (song_id));
+diesel::joinable!(song_block -> block (block_id));
+diesel::joinable!(song_block -> song (song_id));
+diesel::joinable!(vamp -> song (song_id));
+diesel::joinable!(word_timing -> lines (line_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     _sqlx_migrations,
+    block,
+    character,
+    cue_log,
+    cue_state,
     lines,
+    output_rule,
+    preset,
+    section,
     song,
+    song_block,
+    vamp,
+    word_timing,
 );
@@ -93,6 +93,138 @@ pub async fn sse_load_song(
     .keep_alive(KeepAlive::default())
 }
 
+pub async fn sse_operator(
+    State(state): State<Store>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut receiver = state.operator_ch.subscribe();
+
+    Sse::new(try_stream! {
+        loop {
+            match receiver.recv().await {
+                Ok(i) => {
+                    let event = Event::default()
+                        .json_data(&i).unwrap();
+
+                    yield event;
+                },
+
+                Err(e) => {
+                    tracing::error!(error = ?e, "Failed to get");
+                }
+            }
+        }
+    })
+    .keep_alive(KeepAlive::default())
+}
+
+pub async fn sse_announcement(
+    State(state): State<Store>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut receiver = state.announcement_ch.subscribe();
+    let current = state.output.read().await.announcement.clone();
+
+    Sse::new(try_stream! {
+        // Displays joining mid-announcement still need to show it
+        yield Event::default().json_data(&current).unwrap();
+
+        loop {
+            match receiver.recv().await {
+                Ok(i) => {
+                    let event = Event::default()
+                        .json_data(&i).unwrap();
+
+                    yield event;
+                },
+
+                Err(e) => {
+                    tracing::error!(error = ?e, "Failed to get");
+                }
+            }
+        }
+    })
+    .keep_alive(KeepAlive::default())
+}
+
+pub async fn sse_countdown(
+    State(state): State<Store>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut receiver = state.countdown_ch.subscribe();
+    let current = state.output.read().await.countdown.clone();
+
+    Sse::new(try_stream! {
+        // Displays joining mid-countdown still need to show it
+        yield Event::default().json_data(&current).unwrap();
+
+        loop {
+            match receiver.recv().await {
+                Ok(i) => {
+                    let event = Event::default()
+                        .json_data(&i).unwrap();
+
+                    yield event;
+                },
+
+                Err(e) => {
+                    tracing::error!(error = ?e, "Failed to get");
+                }
+            }
+        }
+    })
+    .keep_alive(KeepAlive::default())
+}
+
+pub async fn sse_cue(
+    State(state): State<Store>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut receiver = state.cue_ch.subscribe();
+
+    Sse::new(try_stream! {
+        loop {
+            match receiver.recv().await {
+                Ok(i) => {
+                    let event = Event::default()
+                        .json_data(&i).unwrap();
+
+                    yield event;
+                },
+
+                Err(e) => {
+                    tracing::error!(error = ?e, "Failed to get");
+                }
+            }
+        }
+    })
+    .keep_alive(KeepAlive::default())
+}
+
+pub async fn sse_frame(
+    State(state): State<Store>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut receiver = state.frame_ch.subscribe();
+    let current = state.output.read().await.frame.clone();
+
+    Sse::new(try_stream! {
+        // Displays joining mid-song still need to show the current lines
+        yield Event::default().json_data(&current).unwrap();
+
+        loop {
+            match receiver.recv().await {
+                Ok(i) => {
+                    let event = Event::default()
+                        .json_data(&i).unwrap();
+
+                    yield event;
+                },
+
+                Err(e) => {
+                    tracing::error!(error = ?e, "Failed to get");
+                }
+            }
+        }
+    })
+    .keep_alive(KeepAlive::default())
+}
+
 pub async fn sse_scene_ready(
     State(state): State<Store>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
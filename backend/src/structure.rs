@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use diesel::{
+    BoolExpressionMethods, Connection, ExpressionMethods, PgConnection, QueryDsl, QueryResult,
+    RunQueryDsl, SelectableHelper,
+};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::{
+    character::apply_speakers,
+    markup::{parse_markup, sanitize},
+    preset::apply_presets,
+    schema::*,
+    timing::load_timings,
+    types::{Block, DbLineComp, LineComp, LineKind, NewDbLineComp, SongStructure, Vector3},
+    Store,
+};
+
+/// Name of the block holding imported lines that come before any header.
+const MAIN_BLOCK: &str = "Main";
+
+/// A block parsed from imported lyrics.
+pub struct ImportBlock {
+    pub name: String,
+    pub lines: Vec<String>,
+}
+
+fn block_header(line: &str) -> Option<&str> {
+    line.strip_prefix('[')?
+        .strip_suffix(']')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// Splits imported lyrics into blocks at `[Name]` header lines.
+///
+/// Returns the blocks and the order they are played in, as indices into the
+/// blocks. A header repeating the name of an earlier block without any lines
+/// of its own plays that block again. Lyrics without headers become a single
+/// block, keeping every line as is.
+pub fn parse_structure(text: &str) -> (Vec<ImportBlock>, Vec<usize>) {
+    let mut parts = vec![ImportBlock {
+        name: MAIN_BLOCK.to_string(),
+        lines: Vec::new(),
+    }];
+
+    for line in text.split("\n").map(str::trim) {
+        match block_header(line) {
+            Some(name) => parts.push(ImportBlock {
+                name: name.to_string(),
+                lines: Vec::new(),
+            }),
+            None => parts.last_mut().unwrap().lines.push(line.to_string()),
+        }
+    }
+
+    // Empty lines around headers only separate the blocks
+    if parts.len() > 1 {
+        for part in &mut parts {
+            while part.lines.last().is_some_and(|line| line.is_empty()) {
+                part.lines.pop();
+            }
+            let leading = part.lines.iter().take_while(|line| line.is_empty()).count();
+            part.lines.drain(..leading);
+        }
+    }
+
+    let mut blocks: Vec<ImportBlock> = Vec::new();
+    let mut order = Vec::new();
+
+    for part in parts {
+        if part.lines.is_empty() {
+            if let Some(index) = blocks
+                .iter()
+                .position(|block| block.name.eq_ignore_ascii_case(&part.name))
+            {
+                order.push(index);
+            }
+            continue;
+        }
+
+        order.push(blocks.len());
+        blocks.push(part);
+    }
+
+    (blocks, order)
+}
+
+/// Checks the markup of every imported line.
+pub fn validate_lines(lines: &[String]) -> Result<(), (StatusCode, &'static str)> {
+    for line in lines {
+        parse_markup(line).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    Ok(())
+}
+
+/// Builds the rows for newly imported lines of a block.
+pub fn new_lines(song_id: i32, block_id: i32, lines: Vec<String>) -> Vec<NewDbLineComp> {
+    lines
+        .into_iter()
+        .map(|line| {
+            // `---` is how a blank screen is written in imported lyrics
+            let (kind, line) = match line.as_str() {
+                "---" => (LineKind::Blank, String::new()),
+                _ => (LineKind::Lyric, sanitize(&line)),
+            };
+
+            let val = LineComp {
+                line,
+                kind,
+                cam_position: Vector3 {
+                    x: 0.0,
+                    y: 10.0,
+                    z: 150.0,
+                },
+                ..Default::default()
+            };
+
+            NewDbLineComp {
+                song_id,
+                block_id,
+                ..NewDbLineComp::from(val)
+            }
+        })
+        .collect()
+}
+
+/// Position in `song_lines` where `line_id` is played for the `occurrence`th time, from 0.
+///
+/// Lines of blocks played several times show up once for every time.
+pub fn play_position(song_lines: &[LineComp], line_id: i32, occurrence: i32) -> Option<usize> {
+    let occurrence = usize::try_from(occurrence).ok()?;
+
+    song_lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.id == line_id)
+        .nth(occurrence)
+        .map(|(position, _)| position)
+}
+
+/// Loads the lines of a song in play order, expanding every block reference.
+pub fn load_song_lines(con: &mut PgConnection, song_id: i32) -> QueryResult<Vec<LineComp>> {
+    let order = load_order(con, song_id)?;
+
+    let block_lines = lines::table
+        .select(DbLineComp::as_select())
+        .filter(lines::block_id.eq_any(&order))
+        .order(lines::id.asc())
+        .load(con)?;
+
+    let mut by_block: HashMap<i32, Vec<LineComp>> = HashMap::new();
+    for line in block_lines {
+        by_block
+            .entry(line.block_id)
+            .or_default()
+            .push(LineComp::from(line));
+    }
+
+    let mut song_lines = order
+        .iter()
+        .filter_map(|block_id| by_block.get(block_id))
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    load_timings(con, &mut song_lines)?;
+    apply_presets(con, &mut song_lines)?;
+    apply_speakers(con, &mut song_lines)?;
+
+    Ok(song_lines)
+}
+
+fn load_order(con: &mut PgConnection, song_id: i32) -> QueryResult<Vec<i32>> {
+    song_block::table
+        .filter(song_block::song_id.eq(song_id))
+        .order(song_block::position.asc())
+        .select(song_block::block_id)
+        .load(con)
+}
+
+fn set_order(con: &mut PgConnection, song_id: i32, order: &[i32]) -> QueryResult<()> {
+    diesel::delete(song_block::table.filter(song_block::song_id.eq(song_id))).execute(con)?;
+
+    let rows = order
+        .iter()
+        .enumerate()
+        .map(|(position, &block_id)| {
+            (
+                song_block::song_id.eq(song_id),
+                song_block::block_id.eq(block_id),
+                song_block::position.eq(position as i32),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    diesel::insert_into(song_block::table)
+        .values(&rows)
+        .execute(con)?;
+
+    Ok(())
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StructureRequest {
+    id: i32,
+}
+
+pub async fn get_structure(
+    State(state): State<Store>,
+    Query(req): Query<StructureRequest>,
+) -> Result<Json<SongStructure>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            let order = load_order(con, req.id)?;
+
+            // Blocks owned by the song, and blocks borrowed from other songs
+            let blocks = block::table
+                .filter(block::song_id.eq(req.id).or(block::id.eq_any(&order)))
+                .order(block::id.asc())
+                .select((block::id, block::name))
+                .load::<(i32, String)>(con)?;
+
+            let block_ids = blocks.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+            let block_lines = lines::table
+                .select(DbLineComp::as_select())
+                .filter(lines::block_id.eq_any(&block_ids))
+                .order(lines::id.asc())
+                .load(con)?;
+
+            let blocks = blocks
+                .into_iter()
+                .map(|(id, name)| Block {
+                    id,
+                    name,
+                    lines: block_lines
+                        .iter()
+                        .filter(|line| line.block_id == id)
+                        .map(|line| LineComp::from(line.clone()))
+                        .collect(),
+                })
+                .collect();
+
+            QueryResult::Ok(SongStructure {
+                song_id: req.id,
+                blocks,
+                order,
+            })
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(structure) => Ok(Json(structure)),
+        Err(e) => {
+            error!("Failed to load structure of song {}: {}", req.id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load structure",
+            ))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SetStructure {
+    song_id: i32,
+    order: Vec<i32>,
+}
+
+pub async fn set_structure(
+    State(state): State<Store>,
+    Json(body): Json<SetStructure>,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let song_id = body.song_id;
+
+    let res = pool
+        .interact(move |con| {
+            con.transaction(|tran| {
+                let known = block::table
+                    .filter(block::id.eq_any(&body.order))
+                    .count()
+                    .get_result::<i64>(tran)?;
+
+                let mut distinct = body.order.clone();
+                distinct.sort_unstable();
+                distinct.dedup();
+
+                if known != distinct.len() as i64 {
+                    return QueryResult::Ok(false);
+                }
+
+                set_order(tran, body.song_id, &body.order)?;
+
+                QueryResult::Ok(true)
+            })
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(true) => {
+            info!("Updated structure of song with id: {}", song_id);
+            Ok(StatusCode::OK)
+        }
+        Ok(false) => Err((StatusCode::BAD_REQUEST, "Unknown block in structure")),
+        Err(e) => {
+            error!("Failed to update structure of song {}: {}", song_id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update structure",
+            ))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewBlock {
+    song_id: i32,
+    name: String,
+    lines: String,
+}
+
+pub async fn add_block(
+    State(state): State<Store>,
+    Json(body): Json<NewBlock>,
+) -> Result<Json<i32>, (StatusCode, &'static str)> {
+    let pool = state.pool.get().await.unwrap();
+
+    let block_lines = body
+        .lines
+        .split("\n")
+        .map(|line| line.trim().to_string())
+        .collect::<Vec<_>>();
+
+    validate_lines(&block_lines)?;
+
+    let res = pool
+        .interact(move |con| {
+            con.transaction(|tran| {
+                let block_id = diesel::insert_into(block::table)
+                    .values((
+                        block::song_id.eq(body.song_id),
+                        block::name.eq(body.name.trim()),
+                    ))
+                    .returning(block::id)
+                    .get_result::<i32>(tran)?;
+
+                diesel::insert_into(lines::table)
+                    .values(&new_lines(body.song_id, block_id, block_lines))
+                    .execute(tran)?;
+
+                QueryResult::Ok(block_id)
+            })
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(block_id) => Ok(Json(block_id)),
+        Err(e) => {
+            error!("Failed to add block: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to add block"))
+        }
+    }
+}
+
+pub async fn delete_block(
+    State(state): State<Store>,
+    Json(body): Json<StructureRequest>,
+) -> StatusCode {
+    let pool = state.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| diesel::delete(block::table.find(body.id)).execute(con))
+        .await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        error!("Failed to delete block with id: {}", body.id);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    info!("Deleted block with id: {}", body.id);
+
+    StatusCode::OK
+}
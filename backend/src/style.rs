@@ -0,0 +1,124 @@
+use axum::{extract::State, http::StatusCode, Json};
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::{schema::song, types::LineStyle, Store};
+
+/// Whether `color` is a hex color the displays understand: `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`.
+pub fn valid_color(color: &str) -> bool {
+    color.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+impl LineStyle {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let colors = [&self.color, &self.outline_color];
+        if colors
+            .into_iter()
+            .flatten()
+            .any(|color| !valid_color(color))
+        {
+            return Err("Colors must be written as #rgb, #rgba, #rrggbb or #rrggbbaa");
+        }
+        if self
+            .font_weight
+            .is_some_and(|weight| !(100..=900).contains(&weight))
+        {
+            return Err("Font weight must be between 100 and 900");
+        }
+        if self.font_scale.is_some_and(|scale| scale <= 0.0) {
+            return Err("Font scale must be positive");
+        }
+        if self.outline_width.is_some_and(|width| width < 0.0) {
+            return Err("Outline width can't be negative");
+        }
+        if self
+            .opacity
+            .is_some_and(|opacity| !(0.0..=1.0).contains(&opacity))
+        {
+            return Err("Opacity must be between 0 and 1");
+        }
+
+        Ok(())
+    }
+
+    /// Fills every field this style leaves unset from `defaults`.
+    pub fn or(self, defaults: &LineStyle) -> LineStyle {
+        LineStyle {
+            color: self.color.or_else(|| defaults.color.clone()),
+            font_weight: self.font_weight.or(defaults.font_weight),
+            font_scale: self.font_scale.or(defaults.font_scale),
+            outline_color: self
+                .outline_color
+                .or_else(|| defaults.outline_color.clone()),
+            outline_width: self.outline_width.or(defaults.outline_width),
+            opacity: self.opacity.or(defaults.opacity),
+        }
+    }
+}
+
+/// Loads the styling a song gives every line that doesn't set its own.
+pub fn load_song_style(con: &mut PgConnection, song_id: i32) -> QueryResult<LineStyle> {
+    song::table
+        .find(song_id)
+        .select((
+            song::color,
+            song::font_weight,
+            song::font_scale,
+            song::outline_color,
+            song::outline_width,
+            song::opacity,
+        ))
+        .first(con)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SongStyle {
+    song_id: i32,
+    #[serde(flatten)]
+    style: LineStyle,
+}
+
+pub async fn set_song_style(
+    State(state): State<Store>,
+    Json(body): Json<SongStyle>,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    body.style
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let song_id = body.song_id;
+    let style = body.style;
+
+    let res = pool
+        .interact(move |con| {
+            diesel::update(song::table.find(song_id))
+                .set((
+                    song::color.eq(style.color),
+                    song::font_weight.eq(style.font_weight),
+                    song::font_scale.eq(style.font_scale),
+                    song::outline_color.eq(style.outline_color),
+                    song::outline_width.eq(style.outline_width),
+                    song::opacity.eq(style.opacity),
+                ))
+                .execute(con)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(0) => Err((StatusCode::NOT_FOUND, "Song not found")),
+        Ok(_) => {
+            info!("Updated style of song with id: {}", song_id);
+            Ok(StatusCode::OK)
+        }
+        Err(e) => {
+            error!("Failed to update style of song {}: {}", song_id, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to update style"))
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{extract::State, http::StatusCode, Json};
+use diesel::{Connection, ExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::{
+    schema::word_timing,
+    structure::load_song_lines,
+    types::{LineComp, LineKind, WordTiming},
+    Store,
+};
+
+/// Fills in the word timings of `song_lines`.
+pub fn load_timings(con: &mut PgConnection, song_lines: &mut [LineComp]) -> QueryResult<()> {
+    let line_ids = song_lines.iter().map(|line| line.id).collect::<Vec<_>>();
+
+    let rows = word_timing::table
+        .filter(word_timing::line_id.eq_any(&line_ids))
+        .order((word_timing::line_id.asc(), word_timing::position.asc()))
+        .select((
+            word_timing::line_id,
+            word_timing::text,
+            word_timing::start_ms,
+            word_timing::end_ms,
+        ))
+        .load::<(i32, String, i32, Option<i32>)>(con)?;
+
+    let mut by_line: HashMap<i32, Vec<WordTiming>> = HashMap::new();
+    for (line_id, text, start_ms, end_ms) in rows {
+        by_line.entry(line_id).or_default().push(WordTiming {
+            text,
+            start_ms: start_ms.max(0) as u32,
+            end_ms: end_ms.map(|end_ms| end_ms.max(0) as u32),
+        });
+    }
+
+    for line in song_lines {
+        line.timings = by_line.get(&line.id).cloned().unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+/// Checks that the words of a line are in order, don't end before they start
+/// and fit the database.
+fn validate(timings: &[WordTiming]) -> Result<(), &'static str> {
+    if timings.iter().any(|word| word.text.trim().is_empty()) {
+        return Err("Timed words can't be empty");
+    }
+    if timings.iter().any(|word| {
+        i32::try_from(word.start_ms).is_err()
+            || word
+                .end_ms
+                .is_some_and(|end_ms| i32::try_from(end_ms).is_err())
+    }) {
+        return Err("Word time out of range");
+    }
+    if timings
+        .iter()
+        .any(|word| word.end_ms.is_some_and(|end_ms| end_ms < word.start_ms))
+    {
+        return Err("A word ends before it starts");
+    }
+    if timings
+        .windows(2)
+        .any(|pair| pair[1].start_ms < pair[0].start_ms)
+    {
+        return Err("Words must be in order");
+    }
+
+    Ok(())
+}
+
+fn set_timings(con: &mut PgConnection, line_id: i32, timings: &[WordTiming]) -> QueryResult<()> {
+    diesel::delete(word_timing::table.filter(word_timing::line_id.eq(line_id))).execute(con)?;
+
+    let rows = timings
+        .iter()
+        .enumerate()
+        .map(|(position, word)| {
+            (
+                word_timing::line_id.eq(line_id),
+                word_timing::position.eq(position as i32),
+                word_timing::text.eq(word.text.trim()),
+                word_timing::start_ms.eq(word.start_ms as i32),
+                word_timing::end_ms.eq(word.end_ms.map(|end_ms| end_ms as i32)),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    diesel::insert_into(word_timing::table)
+        .values(&rows)
+        .execute(con)?;
+
+    Ok(())
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SetTimings {
+    line_id: i32,
+    /// An empty list removes the timings, the line is shown without highlighting.
+    timings: Vec<WordTiming>,
+}
+
+pub async fn set_line_timings(
+    State(state): State<Store>,
+    Json(body): Json<SetTimings>,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    validate(&body.timings).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let pool = state.pool.get().await.unwrap();
+
+    let line_id = body.line_id;
+
+    let res = pool
+        .interact(move |con| con.transaction(|tran| set_timings(tran, body.line_id, &body.timings)))
+        .await
+        .unwrap();
+
+    match res {
+        Ok(()) => {
+            info!("Updated word timings of line with id: {}", line_id);
+            Ok(StatusCode::OK)
+        }
+        Err(e) => {
+            error!("Failed to update word timings of line {}: {}", line_id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update timings",
+            ))
+        }
+    }
+}
+
+/// Parses a `[mm:ss.xx]` or `<mm:ss.xx>` timestamp into milliseconds, `None` if
+/// it isn't one. Fails on times that don't fit the database.
+fn lrc_timestamp(stamp: &str) -> Option<Result<u32, &'static str>> {
+    let (minutes, seconds) = stamp.split_once(':')?;
+    let minutes = minutes.trim().parse::<u32>().ok()?;
+    let seconds = seconds.trim().parse::<f64>().ok()?;
+
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+
+    let ms = minutes
+        .checked_mul(60_000)
+        .and_then(|ms| ms.checked_add((seconds * 1000.0).round() as u32))
+        .filter(|&ms| i32::try_from(ms).is_ok());
+
+    Some(ms.ok_or("LRC timestamp out of range"))
+}
+
+/// Parses one line of enhanced LRC, `[00:12.00] <00:12.00> Hel <00:12.40> lo <00:13.10>`,
+/// into word timings relative to the start of the line.
+///
+/// Returns `None` for metadata like `[ar: ...]` and lines without a timestamp.
+/// Lines without word timestamps become a single word spanning the line.
+fn parse_lrc_line(line: &str) -> Result<Option<Vec<WordTiming>>, &'static str> {
+    let Some((stamp, mut rest)) = line
+        .trim()
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+    else {
+        return Ok(None);
+    };
+    let Some(line_start) = lrc_timestamp(stamp).transpose()? else {
+        return Ok(None);
+    };
+
+    let mut words: Vec<WordTiming> = Vec::new();
+    let mut current: Option<u32> = None;
+
+    loop {
+        let (text, stamp) = match rest.split_once('<') {
+            Some((text, tail)) => {
+                let Some((stamp, tail)) = tail.split_once('>') else {
+                    return Ok(None);
+                };
+                let Some(stamp) = lrc_timestamp(stamp).transpose()? else {
+                    return Ok(None);
+                };
+                rest = tail;
+                (text, Some(stamp))
+            }
+            None => (rest, None),
+        };
+
+        let text = text.trim();
+        if !text.is_empty() {
+            words.push(WordTiming {
+                text: text.to_string(),
+                start_ms: current.unwrap_or(line_start).saturating_sub(line_start),
+                end_ms: None,
+            });
+        }
+
+        // A timestamp ends the word before it and starts the next one
+        if let (Some(stamp), Some(last)) = (stamp, words.last_mut()) {
+            if last.end_ms.is_none() && !text.is_empty() {
+                last.end_ms = Some(stamp.saturating_sub(line_start));
+            }
+        }
+
+        match stamp {
+            Some(stamp) => current = Some(stamp),
+            None => break,
+        }
+    }
+
+    Ok(Some(words))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportLrc {
+    song_id: i32,
+    lrc: String,
+}
+
+/// Imports word timings from enhanced LRC.
+///
+/// Timed LRC lines are matched in order to the lines of the song that have
+/// text on the displays, every time they are played. Lines of blocks played
+/// several times are shared, so only the first time a block is played takes
+/// timings from the file and its repeats are skipped.
+pub async fn import_lrc(
+    State(state): State<Store>,
+    Json(body): Json<ImportLrc>,
+) -> Result<Json<usize>, (StatusCode, &'static str)> {
+    let lrc_lines = body
+        .lrc
+        .lines()
+        .filter_map(|line| parse_lrc_line(line).transpose())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?
+        .into_iter()
+        .filter(|timings| !timings.is_empty())
+        .collect::<Vec<_>>();
+
+    if lrc_lines.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "No timed lines found"));
+    }
+    for timings in &lrc_lines {
+        validate(timings).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    let pool = state.pool.get().await.unwrap();
+
+    let song_id = body.song_id;
+
+    let res = pool
+        .interact(move |con| {
+            con.transaction(|tran| {
+                let song_lines = load_song_lines(tran, song_id)?;
+
+                let targets = song_lines.iter().filter(|line| {
+                    matches!(line.kind, LineKind::Lyric | LineKind::TitleCard)
+                        && !line.line.trim().is_empty()
+                });
+
+                let mut seen = HashSet::new();
+                let mut imported = 0;
+                for (line, timings) in targets.zip(&lrc_lines) {
+                    if seen.insert(line.id) {
+                        set_timings(tran, line.id, timings)?;
+                        imported += 1;
+                    }
+                }
+
+                QueryResult::Ok(imported)
+            })
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(imported) => {
+            info!(
+                "Imported word timings for {} lines of song {}",
+                imported, song_id
+            );
+            Ok(Json(imported))
+        }
+        Err(e) => {
+            error!("Failed to import word timings of song {}: {}", song_id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to import timings",
+            ))
+        }
+    }
+}
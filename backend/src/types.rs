@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use diesel::{
     prelude::{AsChangeset, Associations, Identifiable, Insertable, Queryable},
     Selectable,
@@ -6,7 +7,11 @@ use pgvector::Vector;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use crate::schema::{lines, song};
+use crate::{
+    camera::CameraPose,
+    markup::{plain_text, spans_or_plain},
+    schema::{character, lines, output_rule, preset, section, song},
+};
 
 #[derive(Debug, Serialize, Clone, Default, TS)]
 #[ts(export)]
@@ -14,6 +19,115 @@ pub struct LoadSong {
     pub id: i32,
     pub title: String,
     pub lines: Vec<LineComp>,
+    pub sections: Vec<Section>,
+    pub vamps: Vec<Vamp>,
+    /// Styling used by every line that doesn't set its own.
+    pub style: LineStyle,
+    /// Every character speaking in the song.
+    pub characters: Vec<Character>,
+}
+
+impl LoadSong {
+    /// Strips everything from the song that must never reach the audience displays.
+    pub fn for_audience(mut self) -> Self {
+        for line in &mut self.lines {
+            match line.audience_text() {
+                Some(text) if !text.is_empty() => line.line = text,
+                _ => {
+                    line.line = String::new();
+                    line.spans.clear();
+                }
+            }
+            line.notes = None;
+            line.hold = false;
+            line.style = line.style.clone().or(&self.style);
+        }
+
+        self
+    }
+}
+
+/// The cue engine state as seen by the operator, including operator-only fields.
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct OperatorCue {
+    pub song_id: i32,
+    pub index: Option<u32>,
+    pub line: Option<LineComp>,
+    /// The loop the cursor is currently inside of, if any.
+    pub vamp: Option<ActiveVamp>,
+    /// When the current line was cued.
+    #[ts(type = "string | null")]
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+/// Sent to the displays every time a line is cued.
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct Cue {
+    pub index: Option<u32>,
+    /// Word timings of the line count from here.
+    #[ts(type = "string")]
+    pub started_at: DateTime<Utc>,
+}
+
+/// Everything of the active song the displays show right now.
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct Frame {
+    pub song_id: i32,
+    pub index: Option<u32>,
+    /// Where the camera starts for the current line.
+    pub camera: Option<CameraPose>,
+    /// The current line last, after the previous lines it keeps.
+    pub lines: Vec<FrameLine>,
+    #[ts(type = "string | null")]
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+/// A line on the displays, styled as the song styles it.
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct FrameLine {
+    pub line_id: i32,
+    /// Position of the line in `LoadSong::lines`.
+    pub line_index: usize,
+    pub current: bool,
+    pub text: String,
+    pub spans: Vec<Span>,
+    pub style: LineStyle,
+    pub position: Vector3,
+    pub end_position: Option<Vector3>,
+    pub rotation: Option<Vector3>,
+}
+
+/// A range of lines repeated until the operator breaks out of it.
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct Vamp {
+    pub id: i32,
+    pub start_line_id: i32,
+    pub end_line_id: i32,
+    /// Which time the song plays `start_line_id` the loop starts at, 0 for the first.
+    pub occurrence: i32,
+    /// Positions of the first and last line of the loop in `LoadSong::lines`.
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Vamp {
+    /// Whether the 1-indexed cursor of the active song is inside the loop.
+    pub fn contains(&self, cursor: u32) -> bool {
+        (self.start + 1..=self.end + 1).contains(&cursor)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct ActiveVamp {
+    pub vamp: Vamp,
+    /// Set once the operator breaks the loop, the next cue past its end leaves it.
+    pub broken: bool,
 }
 
 #[derive(Debug, Queryable, Selectable, PartialEq, Identifiable)]
@@ -23,28 +137,95 @@ pub struct DbLoadSong {
     pub name: String,
 }
 
+/// A reusable run of lines, e.g. a chorus, that a song can play several times.
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct Block {
+    pub id: i32,
+    pub name: String,
+    pub lines: Vec<LineComp>,
+}
+
+/// The blocks of a song and the order they are played in.
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct SongStructure {
+    pub song_id: i32,
+    pub blocks: Vec<Block>,
+    /// Block ids in play order, a block may appear several times.
+    pub order: Vec<i32>,
+}
+
+/// A named marker (verse, chorus, bridge...) at the line where a part of the song starts.
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct Section {
+    pub id: i32,
+    pub name: String,
+    pub line_id: i32,
+    /// Which time the song plays `line_id` the section starts at, 0 for the first.
+    pub occurrence: i32,
+    /// Position of the first line of the section in `LoadSong::lines`.
+    pub index: u32,
+}
+
+#[derive(Debug, Queryable, Selectable, Associations, Identifiable)]
+#[diesel(table_name = section)]
+#[diesel(belongs_to(DbLoadSong, foreign_key = song_id))]
+pub struct DbSection {
+    pub id: i32,
+    pub song_id: i32,
+    pub name: String,
+    pub line_id: i32,
+    pub occurrence: i32,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default, TS)]
 #[ts(export)]
 pub struct LineComp {
     pub id: i32,
+    /// The line as written, including markup.
     pub line: String,
+    /// The line parsed into styled text, what the displays render.
+    #[serde(default)]
+    pub spans: Vec<Span>,
+    /// When each word is sung, for karaoke-style highlighting. Empty if the line isn't timed.
+    #[serde(default)]
+    pub timings: Vec<WordTiming>,
+    #[serde(default)]
+    pub kind: LineKind,
+    /// Operator-only notes, never sent to the displays.
+    pub notes: Option<String>,
+    /// Tells the operator to wait for a cue from the stage before advancing past this line.
+    #[serde(default)]
+    pub hold: bool,
+    /// The block the line belongs to, shared by every place the block is played.
+    #[serde(default)]
+    pub block_id: i32,
+    /// The character singing or speaking the line.
+    pub speaker_id: Option<i32>,
+    /// Takes the camera and text position from the preset instead of the line's own.
+    pub preset_id: Option<i32>,
     pub position: Vector3,
     pub cam_look_at: Vector3,
     pub cam_position: Vector3,
     pub cam_position_duration: Option<i32>,
     pub cam_end_position: Option<Vector3>,
-    pub color: Option<String>,
+    #[serde(flatten)]
+    #[ts(flatten)]
+    pub style: LineStyle,
     pub keep_n_last: i32,
     pub rotation: Option<Vector3>,
     pub cam_rotation: Option<Vector3>,
 
     // Animation values
-    pub text_animation: Option<AnimationType>,
+    pub animation: Option<Animation>,
+    pub text_position_duration: Option<i32>,
     pub end_position: Option<Vector3>,
     pub cam_end_look_at: Option<Vector3>,
 }
 
-#[derive(Debug, Queryable, Selectable, Associations, Identifiable)]
+#[derive(Debug, Clone, Queryable, Selectable, Associations, Identifiable)]
 #[diesel(table_name = lines)]
 #[diesel(belongs_to(DbLoadSong, foreign_key = song_id))]
 pub struct DbLineComp {
@@ -60,73 +241,375 @@ pub struct DbLineComp {
     pub cam_rotation: Option<Vector>,
 
     pub end_position: Option<Vector>,
-    pub text_animation: Option<AnimationType>,
+    pub animation: Option<serde_json::Value>,
     pub text_position_duration: Option<i32>,
     pub cam_end_position: Option<Vector>,
     pub cam_end_look_at: Option<Vector>,
+    pub kind: LineKind,
+    pub notes: Option<String>,
+    pub hold: bool,
+    pub block_id: i32,
+    #[diesel(embed)]
+    pub style: LineStyle,
+    pub speaker_id: Option<i32>,
+    pub preset_id: Option<i32>,
 }
 
-#[derive(Debug, Insertable, Associations, AsChangeset)]
+#[derive(Debug, Insertable, Associations)]
 #[diesel(table_name = lines)]
 #[diesel(belongs_to(LoadSong, foreign_key = song_id))]
 pub struct NewDbLineComp {
     pub line: String,
+    pub kind: LineKind,
+    pub notes: Option<String>,
+    pub hold: bool,
     pub song_id: i32,
+    pub block_id: i32,
+    pub speaker_id: Option<i32>,
+    pub preset_id: Option<i32>,
     pub position: Vector,
     pub cam_position: Vector,
+    pub cam_position_duration: Option<i32>,
     pub cam_look_at: Vector,
     pub keep_n_last: i32,
+    pub rotation: Option<Vector>,
+    pub cam_rotation: Option<Vector>,
     pub end_position: Option<Vector>,
+    pub animation: Option<serde_json::Value>,
+    pub text_position_duration: Option<i32>,
     pub cam_end_position: Option<Vector>,
     pub cam_end_look_at: Option<Vector>,
+    #[diesel(embed)]
+    pub style: LineStyle,
 }
 
 impl From<DbLineComp> for LineComp {
     fn from(value: DbLineComp) -> Self {
         LineComp {
             id: value.id,
+            spans: spans_or_plain(&value.line),
+            // Stored in their own table, see `timing::load_timings`
+            timings: Vec::new(),
             line: value.line,
+            kind: value.kind,
+            notes: value.notes,
+            hold: value.hold,
+            block_id: value.block_id,
+            speaker_id: value.speaker_id,
+            preset_id: value.preset_id,
             position: value.position.into(),
             cam_look_at: value.cam_look_at.into(),
             cam_position: value.cam_position.into(),
             end_position: value.end_position.map(|v| v.into()),
             keep_n_last: value.keep_n_last,
-            text_animation: value.text_animation,
+            // Rows the current animation model can't read play without animation
+            animation: value
+                .animation
+                .and_then(|animation| serde_json::from_value(animation).ok()),
             cam_position_duration: value.cam_position_duration,
+            text_position_duration: value.text_position_duration,
             cam_end_position: value.cam_end_position.map(|v| v.into()),
             cam_end_look_at: value.cam_end_look_at.map(|v| v.into()),
-            color: None,
+            style: value.style,
             rotation: value.rotation.map(|v| v.into()),
             cam_rotation: value.cam_rotation.map(|v| v.into()),
         }
     }
 }
 
-#[derive(Debug, diesel_derive_enum::DbEnum, Serialize, Deserialize, Clone, TS)]
-#[ExistingTypePath = "crate::schema::sql_types::Animation"]
-pub enum AnimationType {
-    In,
-    Out,
-    InOut,
+/// How the text of a line enters and leaves the displays.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default, TS)]
+#[ts(export)]
+pub struct Animation {
+    /// Played when the line is cued, `None` shows the text at once.
+    pub enter: Option<AnimationStep>,
+    /// Played when the next line is cued, `None` removes the text at once.
+    pub exit: Option<AnimationStep>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct AnimationStep {
+    pub effect: AnimationEffect,
+    #[serde(default)]
+    pub easing: Easing,
+    pub duration_ms: u32,
+    #[serde(default)]
+    pub delay_ms: u32,
+    /// Starts the effect this much later for every following character.
+    pub stagger_ms: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[ts(export)]
+pub enum AnimationEffect {
+    Fade,
+    /// Reveals the text one character at a time.
+    Typewriter,
+    /// Moves the text from (enter) or to (exit) its position plus `offset`.
+    Slide {
+        offset: Vector3,
+    },
+    /// Scales the text from (enter) or to (exit) `scale` times its size.
+    Scale {
+        scale: f32,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[ts(export)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+    /// Same as the CSS `cubic-bezier()` function.
+    CubicBezier {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+    },
+}
+
+impl Animation {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        for step in [&self.enter, &self.exit].into_iter().flatten() {
+            if let AnimationEffect::Scale { scale } = step.effect {
+                if scale < 0.0 {
+                    return Err("Animation scale can't be negative");
+                }
+            }
+            if let Easing::CubicBezier { x1, x2, .. } = step.easing {
+                if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+                    return Err("Cubic bezier x values must be between 0 and 1");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(
+    Debug,
+    diesel_derive_enum::DbEnum,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    TS,
+)]
+#[ExistingTypePath = "crate::schema::sql_types::LineKind"]
+pub enum LineKind {
+    #[default]
+    Lyric,
+    Blank,
+    TitleCard,
+    /// Only shown to the operator, never broadcast to the displays.
+    StageDirection,
+    Announcement,
+}
+
+impl LineKind {
+    /// What the displays show for a line of this kind, `None` if it is never broadcast.
+    pub fn audience_text(self, line: &str) -> Option<&str> {
+        match self {
+            LineKind::Blank => Some(""),
+            LineKind::StageDirection => None,
+            LineKind::Lyric | LineKind::TitleCard | LineKind::Announcement => Some(line),
+        }
+    }
 }
 
 impl From<LineComp> for NewDbLineComp {
     fn from(value: LineComp) -> Self {
         NewDbLineComp {
             line: value.line,
+            kind: value.kind,
+            notes: value.notes,
+            hold: value.hold,
             song_id: 0,
+            block_id: value.block_id,
+            speaker_id: value.speaker_id,
+            preset_id: value.preset_id,
             position: value.position.into(),
             cam_look_at: value.cam_look_at.into(),
             cam_position: value.cam_position.into(),
+            cam_position_duration: value.cam_position_duration,
             keep_n_last: value.keep_n_last,
+            rotation: value.rotation.map(|v| v.into()),
+            cam_rotation: value.cam_rotation.map(|v| v.into()),
             end_position: value.end_position.map(|v| v.into()),
+            animation: value
+                .animation
+                .and_then(|animation| serde_json::to_value(animation).ok()),
+            text_position_duration: value.text_position_duration,
             cam_end_position: value.cam_end_position.map(|v| v.into()),
             cam_end_look_at: value.cam_end_look_at.map(|v| v.into()),
+            style: value.style,
         }
     }
 }
 
-#[derive(Debug, Default, Deserialize, Serialize, Clone, TS)]
+impl LineComp {
+    /// What the displays show for this line without styling, `None` if it is never broadcast.
+    pub fn audience_text(&self) -> Option<String> {
+        let text = plain_text(&self.spans);
+        self.kind.audience_text(&text).map(str::to_string)
+    }
+}
+
+/// A run of text within a line sharing the same styling, or a line break.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[ts(export)]
+pub enum Span {
+    Text {
+        text: String,
+        bold: bool,
+        italic: bool,
+        emphasis: bool,
+    },
+    Break,
+}
+
+/// A word or syllable of a line and when it is sung, counted from when the line is cued.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct WordTiming {
+    pub text: String,
+    pub start_ms: u32,
+    /// `None` keeps the word highlighted until the next one starts.
+    pub end_ms: Option<u32>,
+}
+
+/// How the text of a line is drawn, every unset field falls back to the song defaults.
+#[derive(
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    Clone,
+    PartialEq,
+    Queryable,
+    Selectable,
+    Insertable,
+    AsChangeset,
+    TS,
+)]
+#[diesel(table_name = lines)]
+#[diesel(treat_none_as_null = true)]
+#[ts(export)]
+pub struct LineStyle {
+    /// Hex color, `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`.
+    pub color: Option<String>,
+    /// CSS font weight, 100 to 900.
+    pub font_weight: Option<i32>,
+    /// Multiplier of the default text size.
+    pub font_scale: Option<f32>,
+    pub outline_color: Option<String>,
+    pub outline_width: Option<f32>,
+    /// 0 is fully transparent, 1 fully opaque.
+    pub opacity: Option<f32>,
+}
+
+/// Someone singing or speaking in the production, e.g. one half of a duet.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct Character {
+    #[serde(default)]
+    pub id: i32,
+    pub name: String,
+    /// Hex color the displays use for the character's lines.
+    pub color: Option<String>,
+    /// Added to the position of every line the character speaks when the
+    /// lines are loaded, see `character::apply_speakers`.
+    #[serde(default)]
+    pub position_offset: Vector3,
+}
+
+#[derive(Debug, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = character)]
+pub struct DbCharacter {
+    pub id: i32,
+    pub name: String,
+    pub color: Option<String>,
+    pub position_offset: Vector,
+}
+
+impl From<DbCharacter> for Character {
+    fn from(value: DbCharacter) -> Self {
+        Character {
+            id: value.id,
+            name: value.name,
+            color: value.color,
+            position_offset: value.position_offset.into(),
+        }
+    }
+}
+
+/// How much text an output, e.g. a narrow side screen, fits on a line.
+#[derive(Debug, Deserialize, Serialize, Clone, Queryable, Selectable, Identifiable, TS)]
+#[diesel(table_name = output_rule)]
+#[ts(export)]
+pub struct OutputRule {
+    #[serde(default)]
+    pub id: i32,
+    pub name: String,
+    /// Characters a row holds.
+    pub max_chars: i32,
+    /// Rows shown at once.
+    pub max_rows: i32,
+}
+
+/// A named camera and text setup lines can share, e.g. "wide" or "close left".
+///
+/// Unset fields leave the values of the line as they are.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct Preset {
+    #[serde(default)]
+    pub id: i32,
+    pub name: String,
+    pub cam_position: Option<Vector3>,
+    pub cam_look_at: Option<Vector3>,
+    pub cam_rotation: Option<Vector3>,
+    /// Position of the text.
+    pub position: Option<Vector3>,
+}
+
+#[derive(Debug, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = preset)]
+pub struct DbPreset {
+    pub id: i32,
+    pub name: String,
+    pub cam_position: Option<Vector>,
+    pub cam_look_at: Option<Vector>,
+    pub cam_rotation: Option<Vector>,
+    pub position: Option<Vector>,
+}
+
+impl From<DbPreset> for Preset {
+    fn from(value: DbPreset) -> Self {
+        Preset {
+            id: value.id,
+            name: value.name,
+            cam_position: value.cam_position.map(|v| v.into()),
+            cam_look_at: value.cam_look_at.map(|v| v.into()),
+            cam_rotation: value.cam_rotation.map(|v| v.into()),
+            position: value.position.map(|v| v.into()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, TS)]
 #[ts(export)]
 pub struct Vector3 {
     pub x: f32,
@@ -157,6 +640,10 @@ impl From<Vec<String>> for LoadSong {
             id: 0,
             title: "Undefined".to_string(),
             lines: value.into_iter().map(LineComp::from).collect(),
+            sections: Vec::new(),
+            vamps: Vec::new(),
+            style: LineStyle::default(),
+            characters: Vec::new(),
         }
     }
 }
@@ -164,6 +651,7 @@ impl From<Vec<String>> for LoadSong {
 impl From<String> for LineComp {
     fn from(value: String) -> Self {
         LineComp {
+            spans: spans_or_plain(&value),
             line: value,
             cam_position: Vector3 {
                 x: 0.0,
@@ -174,3 +662,50 @@ impl From<String> for LineComp {
         }
     }
 }
+
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, TS,
+)]
+pub enum AnnouncementLevel {
+    #[default]
+    Normal,
+    /// Replaces everything else on the displays and can't be replaced by a normal announcement.
+    Emergency,
+}
+
+/// Free text shown on the displays instead of the song, e.g. "Pause - 15 minutes".
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct Announcement {
+    pub id: u64,
+    pub text: String,
+    pub color: Option<String>,
+    pub level: AnnouncementLevel,
+    /// When the announcement clears by itself and the previous cue is restored.
+    #[ts(type = "string | null")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, TS)]
+pub enum CountdownMode {
+    /// Counts down to `target`, then returns to the setlist.
+    #[default]
+    Countdown,
+    /// Shows the current time of day until cancelled.
+    Clock,
+}
+
+/// A live countdown or clock the displays render themselves from the target time.
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct Countdown {
+    pub id: u64,
+    pub mode: CountdownMode,
+    pub label: Option<String>,
+    pub color: Option<String>,
+    /// When the countdown reaches zero, `None` while paused.
+    #[ts(type = "string | null")]
+    pub target: Option<DateTime<Utc>>,
+    /// Time left while paused.
+    pub remaining_ms: Option<i64>,
+}
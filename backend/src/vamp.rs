@@ -0,0 +1,222 @@
+use axum::{extract::State, http::StatusCode, Json};
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::{
+    controller::operator_cue,
+    cue_state::save_cue_state,
+    schema::vamp,
+    structure::{load_song_lines, play_position},
+    types::{ActiveVamp, LineComp, Vamp},
+    ActiveSong, Store,
+};
+
+/// Resolves a loop to positions in `song_lines`, the start being the
+/// `occurrence`th time its start line is played and the end the first time
+/// its end line is played at or after the start.
+fn resolve(
+    song_lines: &[LineComp],
+    start_line_id: i32,
+    occurrence: i32,
+    end_line_id: i32,
+) -> Option<(u32, u32)> {
+    let start = play_position(song_lines, start_line_id, occurrence)?;
+    let end = song_lines[start..]
+        .iter()
+        .position(|line| line.id == end_line_id)?
+        + start;
+
+    Some((start as u32, end as u32))
+}
+
+/// Loads the loops of a song, ordered by where they start in `song_lines`.
+pub fn load_vamps(
+    con: &mut PgConnection,
+    song_id: i32,
+    song_lines: &[LineComp],
+) -> QueryResult<Vec<Vamp>> {
+    let rows = vamp::table
+        .filter(vamp::song_id.eq(song_id))
+        .select((
+            vamp::id,
+            vamp::start_line_id,
+            vamp::occurrence,
+            vamp::end_line_id,
+        ))
+        .load::<(i32, i32, i32, i32)>(con)?;
+
+    let mut vamps = rows
+        .into_iter()
+        .filter_map(|(id, start_line_id, occurrence, end_line_id)| {
+            let (start, end) = resolve(song_lines, start_line_id, occurrence, end_line_id)?;
+
+            Some(Vamp {
+                id,
+                start_line_id,
+                end_line_id,
+                occurrence,
+                start,
+                end,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    vamps.sort_by_key(|vamp| vamp.start);
+
+    Ok(vamps)
+}
+
+/// Applies the loops of the song to a move of the cursor to `target`.
+///
+/// Moving past the end of the loop the cursor is in wraps back around to its
+/// start, unless the operator has broken out of that loop.
+pub fn wrap_target(vamps: &[Vamp], active_song: &ActiveSong, target: u32) -> u32 {
+    let Some(vamp) = vamps.iter().find(|vamp| vamp.contains(active_song.line)) else {
+        return target;
+    };
+
+    let (start, end) = (vamp.start + 1, vamp.end + 1);
+
+    if target <= end || active_song.broken_vamp == Some(vamp.id) {
+        return target;
+    }
+
+    start + (target - start) % (end - start + 1)
+}
+
+/// Forgets a broken loop once the cursor has left it, so it loops again next time.
+pub fn leave_vamps(vamps: &[Vamp], active_song: &mut ActiveSong) {
+    let inside_broken = vamps
+        .iter()
+        .any(|vamp| Some(vamp.id) == active_song.broken_vamp && vamp.contains(active_song.line));
+
+    if !inside_broken {
+        active_song.broken_vamp = None;
+    }
+}
+
+pub fn active_vamp(vamps: &[Vamp], active_song: &ActiveSong) -> Option<ActiveVamp> {
+    vamps
+        .iter()
+        .find(|vamp| vamp.contains(active_song.line))
+        .map(|vamp| ActiveVamp {
+            vamp: vamp.clone(),
+            broken: active_song.broken_vamp == Some(vamp.id),
+        })
+}
+
+#[derive(Deserialize)]
+pub struct NewVamp {
+    song_id: i32,
+    start_line_id: i32,
+    end_line_id: i32,
+    /// Which time the song plays the start line, for lines of blocks played more than once.
+    #[serde(default)]
+    occurrence: i32,
+}
+
+pub async fn add_vamp(
+    State(store): State<Store>,
+    Json(body): Json<NewVamp>,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    let pool = store.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| {
+            let song_lines = load_song_lines(con, body.song_id)?;
+
+            let resolved = resolve(
+                &song_lines,
+                body.start_line_id,
+                body.occurrence,
+                body.end_line_id,
+            );
+            if resolved.is_none() {
+                return QueryResult::Ok(false);
+            }
+
+            diesel::insert_into(vamp::table)
+                .values((
+                    vamp::song_id.eq(body.song_id),
+                    vamp::start_line_id.eq(body.start_line_id),
+                    vamp::end_line_id.eq(body.end_line_id),
+                    vamp::occurrence.eq(body.occurrence),
+                ))
+                .execute(con)?;
+
+            QueryResult::Ok(true)
+        })
+        .await
+        .unwrap();
+
+    match res {
+        Ok(true) => Ok(StatusCode::CREATED),
+        Ok(false) => Err((
+            StatusCode::BAD_REQUEST,
+            "Loop must start and end on lines of the song, in order",
+        )),
+        Err(e) => {
+            error!("Failed to add loop: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to add loop"))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DeleteVamp {
+    id: i32,
+}
+
+pub async fn delete_vamp(State(store): State<Store>, Json(body): Json<DeleteVamp>) -> StatusCode {
+    let pool = store.pool.get().await.unwrap();
+
+    let res = pool
+        .interact(move |con| diesel::delete(vamp::table.find(body.id)).execute(con))
+        .await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        error!("Failed to delete loop with id: {}", body.id);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    info!("Deleted loop with id: {}", body.id);
+
+    StatusCode::OK
+}
+
+/// Lets the next cue past the end of the current loop leave it.
+pub async fn break_vamp(State(state): State<Store>) -> StatusCode {
+    let pool = state.pool.get().await.unwrap();
+
+    let mut active_song = state.active_song.write().await;
+
+    let song_id = active_song.id;
+
+    let (song_lines, vamps) = pool
+        .interact(move |con| {
+            let song_lines = load_song_lines(con, song_id)?;
+            let vamps = load_vamps(con, song_id, &song_lines)?;
+
+            QueryResult::Ok((song_lines, vamps))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let Some(vamp) = vamps.iter().find(|vamp| vamp.contains(active_song.line)) else {
+        return StatusCode::CONFLICT;
+    };
+
+    info!("Breaking out of loop with id: {}", vamp.id);
+
+    active_song.broken_vamp = Some(vamp.id);
+
+    let _ = state
+        .operator_ch
+        .send(operator_cue(&active_song, &song_lines, &vamps));
+
+    save_cue_state(&pool, *active_song).await;
+
+    StatusCode::OK
+}
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Vector3 } from "./Vector3";
+
+export type AnimationEffect = { "type": "fade" } | { "type": "typewriter" } | { "type": "slide", offset: Vector3, } | { "type": "scale", scale: number, };
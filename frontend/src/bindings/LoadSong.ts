@@ -1,4 +1,16 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Character } from "./Character";
 import type { LineComp } from "./LineComp";
+import type { LineStyle } from "./LineStyle";
+import type { Section } from "./Section";
+import type { Vamp } from "./Vamp";
 
-export type LoadSong = { id: number, title: string, lines: Array<LineComp>, };
+export type LoadSong = { id: number, title: string, lines: Array<LineComp>, sections: Array<Section>, vamps: Array<Vamp>, 
+/**
+ * Styling used by every line that doesn't set its own.
+ */
+style: LineStyle, 
+/**
+ * Every character speaking in the song.
+ */
+characters: Array<Character>, };
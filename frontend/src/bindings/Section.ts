@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A named marker (verse, chorus, bridge...) at the line where a part of the song starts.
+ */
+export type Section = { id: number, name: string, line_id: number, 
+/**
+ * Which time the song plays `line_id` the section starts at, 0 for the first.
+ */
+occurrence: number, 
+/**
+ * Position of the first line of the section in `LoadSong::lines`.
+ */
+index: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A run of text within a line sharing the same styling, or a line break.
+ */
+export type Span = { "type": "text", text: string, bold: boolean, italic: boolean, emphasis: boolean, } | { "type": "break" };
@@ -1,8 +1,8 @@
 import { type ClassValue, clsx } from "clsx";
-import { cubicIn, cubicInOut, cubicOut } from "svelte/easing";
+import { cubicIn, cubicInOut, cubicOut, linear } from "svelte/easing";
 import { twMerge } from "tailwind-merge";
 import { Vector3 } from "three";
-import type { AnimationType } from "./bindings/AnimationType";
+import type { Easing } from "./bindings/Easing";
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
@@ -17,16 +17,19 @@ export function textToVector(text: string): Vector3 | null {
 }
 
 export function animateConversion(
-  animType: AnimationType | null,
+  easing: Easing | null | undefined,
 ): ((t: number) => number) | undefined {
-  switch (animType) {
-    case "In":
+  switch (easing?.type) {
+    case "linear":
+      return linear;
+
+    case "ease_in":
       return cubicIn;
 
-    case "Out":
+    case "ease_out":
       return cubicOut;
 
-    case "InOut":
+    case "ease_in_out":
       return cubicInOut;
 
     default: